@@ -1,24 +1,258 @@
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, ItemFn};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Attribute, Expr, ExprLit, FnArg, Ident, ItemFn, Lit, Meta, Pat, Path,
+    Signature, Stmt, Token, Type, Visibility,
+};
 
 #[proc_macro_attribute]
 pub fn function_to_string(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Parse input function
     let func: ItemFn = parse_macro_input!(item as ItemFn);
 
-    // Get function body
-    let func_body = format!("{}", func.to_token_stream());
+    prompt_function(&func).into()
+}
 
-    // Define a new function with same signature as the input function
-    let func_ident = func.sig.ident;
-    let func_generics = func.sig.generics;
-    let func_inputs = func.sig.inputs;
+// #[ai_function(output = Type, helper = path, checked)], only output is required
+struct AiFunctionArgs {
+    output: Type,
+    helper: Path,
+    is_checked: bool,
+}
+
+impl Parse for AiFunctionArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut output: Option<Type> = None;
+        let mut helper: Path = syn::parse_quote!(crate::helper);
+        let mut is_checked = false;
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key == "output" {
+                input.parse::<Token![=]>()?;
+                output = Some(input.parse()?);
+            } else if key == "helper" {
+                input.parse::<Token![=]>()?;
+                helper = input.parse()?;
+            } else if key == "checked" {
+                is_checked = true;
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `output = Type`, `helper = path` or `checked`",
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        match output {
+            Some(output) => Ok(Self {
+                output,
+                helper,
+                is_checked,
+            }),
+            None => Err(input.error("ai_function needs `output = Type`")),
+        }
+    }
+}
+
+// Same prompt function as function_to_string, plus `ai_<name>` that asks for the output type:
+// its JSON Schema is sent with the prompt and the answer is validated against it.
+// The wrapper calls into the helper module, crate::helper unless `helper = path` names another
+// one, which must provide:
+//   structured::StructuredError
+//   general::ai_structured_request::<T>(String, &str, &str, fn(&str) -> &'static str)
+//   general::ai_checked_request::<T, C>(.., check: C), only for `checked`
+// With `checked` the wrapper takes a `check: impl Fn(&T) -> Vec<String>` for what the schema
+// cannot express
+#[proc_macro_attribute]
+pub fn ai_function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args: AiFunctionArgs = parse_macro_input!(attr as AiFunctionArgs);
+    let func: ItemFn = parse_macro_input!(item as ItemFn);
+
+    // The prompt takes exactly one input, the text the task works on
+    let input_ident = match func.sig.inputs.iter().collect::<Vec<&FnArg>>().as_slice() {
+        [FnArg::Typed(input)] => match input.pat.as_ref() {
+            Pat::Ident(pat) => pat.ident.clone(),
+            _ => {
+                return syn::Error::new_spanned(&input.pat, "ai_function input must be a name")
+                    .to_compile_error()
+                    .into()
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(&func.sig.inputs, "ai_function takes exactly one input")
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let prompt = prompt_function(&func);
+    let func_ident = &func.sig.ident;
+    let func_inputs = &func.sig.inputs;
+    let wrapper_ident = format_ident!("ai_{}", func_ident);
+    let output_type = &args.output;
+    let helper = &args.helper;
+
+    let wrapper = if args.is_checked {
+        quote! {
+            pub async fn #wrapper_ident(
+                #func_inputs,
+                agent_position: &str,
+                agent_task: &str,
+                check: impl Fn(&#output_type) -> Vec<String>,
+            ) -> Result<#output_type, #helper::structured::StructuredError> {
+                #helper::general::ai_checked_request::<#output_type, _>(
+                    ::std::string::ToString::to_string(&#input_ident),
+                    agent_position,
+                    agent_task,
+                    #func_ident,
+                    check,
+                )
+                .await
+            }
+        }
+    } else {
+        quote! {
+            pub async fn #wrapper_ident(
+                #func_inputs,
+                agent_position: &str,
+                agent_task: &str,
+            ) -> Result<#output_type, #helper::structured::StructuredError> {
+                #helper::general::ai_structured_request::<#output_type>(
+                    ::std::string::ToString::to_string(&#input_ident),
+                    agent_position,
+                    agent_task,
+                    #func_ident,
+                )
+                .await
+            }
+        }
+    };
 
     let output = quote! {
+        #prompt
+
+        #wrapper
+    };
+    output.into()
+}
+
+fn prompt_function(func: &ItemFn) -> TokenStream2 {
+    // Get function source as the prompt
+    let func_body = prompt_text(func);
+
+    // Define a new function with same signature as the input function
+    let func_ident = &func.sig.ident;
+    let func_generics = &func.sig.generics;
+    let func_inputs = &func.sig.inputs;
+
+    quote! {
         pub fn #func_ident #func_generics(#func_inputs) -> &'static str {
             #func_body
         }
+    }
+}
+
+// The function as it was written: doc comments stay /// lines with their own text, other
+// attributes are dropped
+fn prompt_text(func: &ItemFn) -> String {
+    let mut lines: Vec<String> = doc_lines(&func.attrs, "");
+    lines.push(format!("{} {{", signature_text(&func.vis, &func.sig)));
+
+    for stmt in &func.block.stmts {
+        let (attrs, code) = match stmt {
+            Stmt::Macro(stmt) => {
+                let semi = if stmt.semi_token.is_some() { ";" } else { "" };
+                let path = path_text(&stmt.mac.path);
+                (
+                    &stmt.attrs[..],
+                    format!("{}!({}){}", path, stmt.mac.tokens, semi),
+                )
+            }
+            Stmt::Expr(Expr::Macro(expr), semi) => {
+                let semi = if semi.is_some() { ";" } else { "" };
+                let path = path_text(&expr.mac.path);
+                (
+                    &expr.attrs[..],
+                    format!("{}!({}){}", path, expr.mac.tokens, semi),
+                )
+            }
+            other => (&[][..], tidy_tokens(&other.to_token_stream().to_string())),
+        };
+        lines.extend(doc_lines(attrs, "    "));
+        lines.push(format!("    {}", code));
+    }
+
+    lines.push(String::from("}"));
+    lines.join("\n")
+}
+
+fn doc_lines(attrs: &[Attribute], indent: &str) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(text),
+                    ..
+                }) => Some(format!("{}///{}", indent, text.value())),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn signature_text(vis: &Visibility, sig: &Signature) -> String {
+    let vis = match vis {
+        Visibility::Inherited => String::new(),
+        other => format!("{} ", tidy_tokens(&other.to_token_stream().to_string())),
+    };
+    let inputs = sig
+        .inputs
+        .iter()
+        .map(|input| tidy_tokens(&input.to_token_stream().to_string()))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let output = match &sig.output {
+        syn::ReturnType::Default => String::new(),
+        syn::ReturnType::Type(_, ty) => {
+            format!(" -> {}", tidy_tokens(&ty.to_token_stream().to_string()))
+        }
     };
-    output.into()
+
+    format!("{}fn {}({}){}", vis, sig.ident, inputs, output)
+}
+
+fn path_text(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<String>>()
+        .join("::")
+}
+
+// Stringified tokens are spaced everywhere, "& str" and "Vec < String >" read back as "&str" and "Vec<String>"
+fn tidy_tokens(tokens: &str) -> String {
+    [
+        (" :: ", "::"),
+        (" : ", ": "),
+        ("& ", "&"),
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        ("( ", "("),
+        (" )", ")"),
+        ("pub (", "pub("),
+    ]
+    .iter()
+    .fold(tokens.to_string(), |text, (from, to)| {
+        text.replace(from, to)
+    })
 }
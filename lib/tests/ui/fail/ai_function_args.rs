@@ -0,0 +1,9 @@
+use lib::ai_function;
+
+#[ai_function(input = String)]
+pub fn print_list(_text_input: &str) {
+    /// Output: Prints ["a", "b"]
+    println!(OUTPUT)
+}
+
+fn main() {}
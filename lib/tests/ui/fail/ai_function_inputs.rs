@@ -0,0 +1,9 @@
+use lib::ai_function;
+
+#[ai_function(output = Vec<String>)]
+pub fn print_pairs(_left: &str, _right: &str) {
+    /// Output: Prints ["a", "b"]
+    println!(OUTPUT)
+}
+
+fn main() {}
@@ -0,0 +1,79 @@
+use std::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use lib::ai_function;
+
+// The layout ai_function expects, at crate::helper unless `helper = path` says otherwise
+mod helper {
+    pub mod structured {
+        #[derive(Debug)]
+        pub struct StructuredError;
+    }
+
+    pub mod general {
+        use super::structured::StructuredError;
+
+        pub async fn ai_structured_request<T: From<String>>(
+            msg: String,
+            _agent_position: &str,
+            _agent_task: &str,
+            _func: fn(&str) -> &'static str,
+        ) -> Result<T, StructuredError> {
+            Ok(T::from(msg))
+        }
+
+        pub async fn ai_checked_request<T: From<String>, C: Fn(&T) -> Vec<String>>(
+            msg: String,
+            _agent_position: &str,
+            _agent_task: &str,
+            _func: fn(&str) -> &'static str,
+            check: C,
+        ) -> Result<T, StructuredError> {
+            let value = T::from(msg);
+            match check(&value).is_empty() {
+                true => Ok(value),
+                false => Err(StructuredError),
+            }
+        }
+    }
+}
+
+mod prompts {
+    use lib::ai_function;
+
+    #[ai_function(output = String, helper = crate::helper)]
+    pub fn print_echo(_text_input: &str) {
+        /// Output: Prints the input
+        println!(OUTPUT)
+    }
+}
+
+#[ai_function(output = String, checked)]
+pub fn print_checked(_text_input: &str) {
+    /// Output: Prints the input
+    println!(OUTPUT)
+}
+
+// The stub helpers never wait, one poll finishes them
+fn ready<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("stub helper is pending"),
+    }
+}
+
+fn main() {
+    assert_eq!(
+        ready(prompts::ai_print_echo("hello", "Tester", "Echo")).unwrap(),
+        "hello"
+    );
+    assert!(ready(ai_print_checked("ok", "Tester", "Check", |_| vec![])).is_ok());
+    assert!(ready(ai_print_checked("bad", "Tester", "Check", |_| vec![
+        String::from("bad")
+    ]))
+    .is_err());
+    let _prompt: fn(&str) -> &'static str = prompts::print_echo;
+}
@@ -0,0 +1,24 @@
+use lib::function_to_string;
+
+/// Outer docs are kept above the signature
+#[allow(dead_code)]
+#[function_to_string]
+pub fn print_shout(_text_input: &str) {
+    /// INPUT: Takes in a "quoted" TEXT_INPUT
+    ///
+    ///   OUTPUT = "SHOUT"
+    println!("{}", OUTPUT);
+}
+
+fn main() {
+    assert_eq!(
+        print_shout("ignored"),
+        "/// Outer docs are kept above the signature
+pub fn print_shout(_text_input: &str) {
+    /// INPUT: Takes in a \"quoted\" TEXT_INPUT
+    ///
+    ///   OUTPUT = \"SHOUT\"
+    println!(\"{}\", OUTPUT);
+}"
+    );
+}
@@ -1,17 +1,28 @@
 use openai_dive::v1::{
     api::Client,
     models::Gpt35Engine,
-    resources::chat::{ChatCompletionParameters, ChatMessage, ChatMessageContent},
+    resources::chat::{
+        ChatCompletionParameters, ChatCompletionResponseFormat, ChatMessage, ChatMessageContent,
+    },
 };
 use std::env;
 
 pub async fn call_gpt(messages: Vec<ChatMessage>) -> String {
+    call_gpt_with_format(messages, None).await
+}
+
+// A json_object response format turns on JSON mode
+pub async fn call_gpt_with_format(
+    messages: Vec<ChatMessage>,
+    response_format: Option<ChatCompletionResponseFormat>,
+) -> String {
     let api_key = env::var("OPENAI_API_KEY").expect("$OPENAI_API_KEY is not set");
     let client = Client::new(api_key);
 
     let parameters = ChatCompletionParameters {
         model: Gpt35Engine::Gpt35Turbo16K.to_string(),
         messages,
+        response_format,
         ..Default::default()
     };
 
@@ -0,0 +1,586 @@
+// import
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::helper::endpoints::{
+    fill_path, is_logout_route, plan_endpoint_tests, EndpointCall, TestFlow, TOKEN_FIELDS,
+};
+use crate::helper::manifest::{add_table_dependencies, Dependency};
+use crate::helper::openapi::to_json_schema;
+use crate::models::agent::pro::pro_trait::RouteObject;
+use crate::models::agent::pro::route_schema::BodySchema;
+
+// const
+pub const API_TESTS_PATH: &str = "tests/api.rs";
+const ID_MARKER: &str = "{id}";
+
+// Checks responses the same way BodySchema::validate does, embedded as its own module so its
+// imports never clash with the header's
+const SCHEMA_CHECK_SOURCE: &str = include_str!("schema_check.rs");
+
+// Helpers of the generated suite: start the server binary, send calls, check response schemas
+const API_TESTS_HEADER: &str = r#"// Generated by plotto from the extracted routes, regenerated on every backend run
+// Helpers the routes do not need stay unused
+#![allow(dead_code)]
+
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::{
+    net::TcpListener,
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use schema_check::check_schema;
+
+// Every test shares the server's on-disk state, so run one server at a time
+static SERVER_LOCK: Mutex<()> = Mutex::new(());
+static NEXT_SUFFIX: AtomicUsize = AtomicUsize::new(0);
+const TOKEN_FIELDS: [&str; __TOKEN_COUNT__] = __TOKEN_FIELDS__;
+
+struct TestServer {
+    child: Child,
+    base_url: String,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(5))
+        .cookie_store(true)
+        .build()
+        .expect("failed to build client")
+}
+
+fn start_server() -> TestServer {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .expect("no free port");
+    let child = Command::new(env!("CARGO_BIN_EXE___BIN__"))
+        .env("PORT", port.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to start the server");
+    let server = TestServer {
+        child,
+        base_url: format!("http://127.0.0.1:{}", port),
+    };
+
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(30) {
+        if client().get(&server.base_url).send().is_ok() {
+            return server;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    panic!("server did not answer on port {}", port);
+}
+
+// One client per test: cookies set by login stay in its store, a returned token is sent as Bearer
+struct Session {
+    client: Client,
+    token: Option<String>,
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            client: client(),
+            token: None,
+        }
+    }
+
+    fn send(&self, server: &TestServer, method: &str, path: &str, body: Option<&Value>) -> Value {
+        let method = reqwest::Method::from_bytes(method.as_bytes()).expect("invalid method");
+        let mut request = self
+            .client
+            .request(method.clone(), format!("{}{}", server.base_url, path));
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .unwrap_or_else(|e| panic!("{} {} failed: {}", method, path, e));
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+        assert!(
+            status.is_success(),
+            "{} {} returned {}: {}",
+            method,
+            path,
+            status,
+            text
+        );
+
+        serde_json::from_str(&text).unwrap_or(Value::Null)
+    }
+
+    fn keep_token(&mut self, response: &Value) {
+        if let Some(token) = find_token(response) {
+            self.token = Some(token);
+        }
+    }
+}
+
+fn parse(json: &str) -> Value {
+    serde_json::from_str(json).expect("invalid JSON in generated test")
+}
+
+fn assert_schema(value: &Value, schema: &str) {
+    let mut errors = vec![];
+    check_schema(value, &parse(schema), "$", &mut errors);
+    assert!(
+        errors.is_empty(),
+        "response does not match schema: {}",
+        errors.join("; ")
+    );
+}
+
+// A bearer token in the login response: {"token": ".."} or {"data": {"access_token": ".."}}
+fn find_token(response: &Value) -> Option<String> {
+    let object = response.as_object()?;
+    TOKEN_FIELDS
+        .iter()
+        .find_map(|field| object.get(*field)?.as_str().map(String::from))
+        .or_else(|| object.values().find_map(find_token))
+}
+
+// Users from earlier runs stay in the server's data, so every test registers its own
+fn unique_suffix() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!(
+        "{}{}",
+        millis % 1_000_000_000,
+        NEXT_SUFFIX.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+// "plotto_username" -> "plotto_username123", "plotto@example.com" -> "plotto123@example.com"
+fn with_suffix(mut body: Value, suffix: &str) -> Value {
+    if let Some(fields) = body.as_object_mut() {
+        for value in fields.values_mut() {
+            if let Value::String(text) = value {
+                *text = match text.split_once('@') {
+                    Some((name, domain)) => format!("{}{}@{}", name, suffix, domain),
+                    None => format!("{}{}", text, suffix),
+                };
+            }
+        }
+    }
+    body
+}
+
+// The id the server returned, or the one we sent
+fn find_id(response: &Value, body: Option<&Value>) -> Option<Value> {
+    let from_object = |value: &Value| {
+        let object = value.as_object()?;
+        object.get("id").cloned().or_else(|| {
+            object
+                .iter()
+                .find(|(key, value)| key.ends_with("_id") && !value.is_null())
+                .map(|(_, value)| value.clone())
+        })
+    };
+
+    from_object(response).or_else(|| body.and_then(from_object))
+}
+
+fn id_text(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+"#;
+
+// Package name from Cargo.toml, which names the binary cargo test builds
+pub fn package_name(cargo_toml: &str) -> Option<String> {
+    let mut is_package = false;
+    for line in cargo_toml.lines().map(str::trim) {
+        if line.starts_with('[') {
+            is_package = line == "[package]";
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            if is_package && key.trim() == "name" {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+
+    None
+}
+
+// "/api/task/{id}" -> "api_task_id"
+fn route_slug(route: &str) -> String {
+    let slug: String = route
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("_");
+
+    if slug.is_empty() {
+        String::from("root")
+    } else {
+        slug
+    }
+}
+
+// Raw string literal that survives any JSON
+fn json_literal(value: &Value) -> String {
+    format!("r##\"{}\"##", value)
+}
+
+// JSON Schema of the response, checked by the embedded check_schema
+fn schema_assertion(schema: &BodySchema) -> Option<String> {
+    match schema {
+        BodySchema::None | BodySchema::NotProvided => None,
+        schema => Some(json_literal(&to_json_schema(schema))),
+    }
+}
+
+// Test name and calls of each flow, in the planned order
+fn group_calls(calls: &[EndpointCall]) -> Vec<(String, Vec<&EndpointCall>)> {
+    let mut groups: Vec<(String, Vec<&EndpointCall>)> = vec![];
+
+    for call in calls {
+        let name = match &call.flow {
+            TestFlow::Auth => String::from("auth_flow"),
+            TestFlow::Crud(resource) => format!("crud_{}", route_slug(resource)),
+            TestFlow::Single => format!("{}_{}", call.route.method, route_slug(&call.route.route)),
+        };
+
+        match groups.iter_mut().find(|(group, _)| *group == name) {
+            Some((_, group_calls)) if call.flow != TestFlow::Single => group_calls.push(call),
+            Some(_) => {
+                let name = format!("{}_{}", name, groups.len() + 1);
+                groups.push((name, vec![call]));
+            }
+            None => groups.push((name, vec![call])),
+        }
+    }
+
+    groups
+}
+
+// login_calls run first, so the test's own calls are made in a logged in session
+fn render_test(name: &str, calls: &[&EndpointCall], login_calls: &[&EndpointCall]) -> String {
+    let resource = match &calls[0].flow {
+        TestFlow::Crud(resource) => Some(resource.clone()),
+        _ => None,
+    };
+    let no_ids: HashMap<String, Value> = HashMap::new();
+    let id_marker: HashMap<String, Value> = resource
+        .iter()
+        .map(|resource| (resource.clone(), Value::from(ID_MARKER)))
+        .collect();
+
+    let mut lines: Vec<String> = vec![
+        String::from("#[test]"),
+        format!("fn {}() {{", name),
+        String::from("    let _lock = SERVER_LOCK.lock().unwrap_or_else(|e| e.into_inner());"),
+        String::from("    let server = start_server();"),
+    ];
+    let has_auth = login_calls
+        .iter()
+        .chain(calls)
+        .any(|call| call.flow == TestFlow::Auth);
+    if has_auth {
+        lines.push(String::from("    let mut session = Session::new();"));
+        lines.push(String::from("    let suffix = unique_suffix();"));
+    } else {
+        lines.push(String::from("    let session = Session::new();"));
+    }
+
+    // Only keep the created id when a later call reads it
+    let is_id_used = calls.iter().skip(1).any(|call| {
+        fill_path(call, &id_marker).contains(ID_MARKER)
+            || call
+                .body
+                .as_ref()
+                .is_some_and(|body| body.get("id").is_some())
+    });
+    let mut has_id = false;
+
+    for call in login_calls.iter().chain(calls) {
+        let is_auth = call.flow == TestFlow::Auth;
+        let method = call.route.method.as_str().to_uppercase();
+        lines.push(String::new());
+        lines.push(format!("    // {} {}", method, call.route.route));
+
+        let default_path = fill_path(call, &no_ids);
+        let id_path = fill_path(call, &id_marker);
+        let path = if has_id && id_path.contains(ID_MARKER) {
+            lines.push(format!(
+                "    let path = match &id {{\n        Some(id) => {:?}.replacen({:?}, &id_text(id), 1),\n        None => String::from({:?}),\n    }};",
+                id_path, ID_MARKER, default_path
+            ));
+            String::from("&path")
+        } else {
+            format!("{:?}", default_path)
+        };
+
+        let body = match &call.body {
+            Some(body) if has_id && body.get("id").is_some() => {
+                lines.push(format!("    let mut body = parse({});", json_literal(body)));
+                lines.push(String::from(
+                    "    if let (Some(id), Some(field)) = (&id, body.get_mut(\"id\")) {\n        *field = id.clone();\n    }",
+                ));
+                String::from("Some(&body)")
+            }
+            Some(body) if is_auth => {
+                lines.push(format!(
+                    "    let body = with_suffix(parse({}), &suffix);",
+                    json_literal(body)
+                ));
+                String::from("Some(&body)")
+            }
+            Some(body) => {
+                lines.push(format!("    let body = parse({});", json_literal(body)));
+                String::from("Some(&body)")
+            }
+            None => String::from("None"),
+        };
+
+        let schema = schema_assertion(&call.route.response);
+        let is_capturing = call.captures_id && is_id_used && !has_id;
+        let send = format!("session.send(&server, {:?}, {}, {})", method, path, body);
+        if schema.is_some() || is_capturing || is_auth {
+            lines.push(format!("    let response = {};", send));
+        } else {
+            lines.push(format!("    {};", send));
+        }
+        if let Some(schema) = schema {
+            lines.push(format!("    assert_schema(&response, {});", schema));
+        }
+        if is_auth {
+            lines.push(String::from("    session.keep_token(&response);"));
+        }
+        if is_capturing {
+            let body = if call.body.is_some() {
+                "Some(&body)"
+            } else {
+                "None"
+            };
+            lines.push(format!("    let id = find_id(&response, {});", body));
+            has_id = true;
+        }
+    }
+
+    lines.push(String::from("}"));
+    lines.join("\n")
+}
+
+// schema_check.rs as `mod schema_check { .. }`
+fn schema_check_module() -> String {
+    let body = SCHEMA_CHECK_SOURCE
+        .trim_end()
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("    {}", line)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!("mod schema_check {{\n{}\n}}", body)
+}
+
+// Whole tests/api.rs: helpers plus one test per auth flow, CRUD resource and single route
+pub fn render_api_tests(
+    routes: &[RouteObject],
+    is_login_required: bool,
+    binary_name: &str,
+) -> String {
+    let calls = plan_endpoint_tests(routes, is_login_required);
+    let header = API_TESTS_HEADER
+        .trim_end()
+        .replace("__BIN__", binary_name)
+        .replace("__TOKEN_COUNT__", &TOKEN_FIELDS.len().to_string())
+        .replace("__TOKEN_FIELDS__", &format!("{:?}", TOKEN_FIELDS));
+    let mut sections: Vec<String> = vec![header, schema_check_module()];
+
+    // Every other test registers and logs in first, as the endpoint run does
+    let login_calls: Vec<&EndpointCall> = calls
+        .iter()
+        .filter(|call| call.flow == TestFlow::Auth && !is_logout_route(&call.route.route))
+        .collect();
+    for (name, group) in group_calls(&calls) {
+        let group_login_calls = match group[0].flow {
+            TestFlow::Auth => &[][..],
+            _ => &login_calls[..],
+        };
+        sections.push(render_test(&name, &group, group_login_calls));
+    }
+
+    let mut rendered = sections.join("\n\n");
+    rendered.push('\n');
+    rendered
+}
+
+// Write tests/api.rs and the dev-dependencies it needs into the generated project
+pub fn write_api_tests(
+    project_path: &str,
+    routes: &[RouteObject],
+    is_login_required: bool,
+) -> std::io::Result<()> {
+    let cargo_toml_path = Path::new(project_path).join("Cargo.toml");
+    let cargo_toml = fs::read_to_string(&cargo_toml_path)?;
+    let binary_name = package_name(&cargo_toml).unwrap_or_else(|| String::from("web_template"));
+
+    let dev_dependencies = vec![
+        Dependency {
+            name: String::from("reqwest"),
+            version: String::from("0.11"),
+            features: vec![
+                String::from("blocking"),
+                String::from("json"),
+                String::from("cookies"),
+            ],
+        },
+        Dependency {
+            name: String::from("serde_json"),
+            version: String::from("1.0"),
+            features: vec![],
+        },
+    ];
+    fs::write(
+        &cargo_toml_path,
+        add_table_dependencies(&cargo_toml, "[dev-dependencies]", &dev_dependencies),
+    )?;
+
+    let tests_path = Path::new(project_path).join(API_TESTS_PATH);
+    if let Some(parent) = tests_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        tests_path,
+        render_api_tests(routes, is_login_required, &binary_name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::agent::pro::route_schema::HttpMethod;
+
+    fn route(method: HttpMethod, path: &str, request_body: Value, response: Value) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: path.contains('{'),
+            method,
+            request_body: BodySchema::from_value(&request_body),
+            response: BodySchema::from_value(&response),
+            route: String::from(path),
+            handler: None,
+            json_body_type: None,
+            path_params_type: path.contains('{').then(|| String::from("u64")),
+            requirement_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn test_package_name() {
+        let cargo_toml = "[package]\nname = \"web_template\"\nversion = \"0.1.0\"\n\n[dependencies]\nname = \"1\"\n";
+        assert_eq!(package_name(cargo_toml), Some(String::from("web_template")));
+        assert_eq!(package_name("[dependencies]\n"), None);
+    }
+
+    #[test]
+    fn test_render_api_tests() {
+        let task = json!({ "id": "number", "title": "string" });
+        let routes = vec![
+            route(HttpMethod::Post, "/task", task.clone(), task.clone()),
+            route(HttpMethod::Get, "/task/{id}", json!("None"), task.clone()),
+            route(HttpMethod::Put, "/task/{id}", task.clone(), task.clone()),
+            route(HttpMethod::Get, "/health", json!("None"), json!("None")),
+        ];
+
+        let rendered = render_api_tests(&routes, false, "web_template");
+
+        assert!(rendered.contains("env!(\"CARGO_BIN_EXE_web_template\")"));
+        assert!(rendered.contains("fn crud_task() {"));
+        assert!(rendered.contains("fn get_health() {"));
+        assert!(rendered.contains("let id = find_id(&response, Some(&body));"));
+        assert!(
+            rendered.contains("Some(id) => \"/task/{id}\".replacen(\"{id}\", &id_text(id), 1),")
+        );
+        assert!(rendered.contains("None => String::from(\"/task/1\"),"));
+        assert!(rendered.contains("let mut body = parse("));
+        assert!(rendered.contains(&format!(
+            "assert_schema(&response, r##\"{}\"##);",
+            to_json_schema(&BodySchema::from_value(&task))
+        )));
+        assert!(rendered.contains("use serde_json::Value;\n"));
+        assert!(rendered.contains(&schema_check_module()));
+        assert!(rendered.contains("    session.send(&server, \"GET\", \"/health\", None);"));
+        assert!(!rendered.contains("fn auth_flow()"));
+        assert!(syn::parse_file(&rendered).is_ok());
+    }
+
+    #[test]
+    fn test_render_api_tests_with_login() {
+        let task = json!({ "id": "number", "title": "string" });
+        let user = json!({ "username": "string", "password": "string" });
+        let routes = vec![
+            route(HttpMethod::Post, "/register", user.clone(), json!("None")),
+            route(HttpMethod::Post, "/login", user, json!("None")),
+            route(HttpMethod::Post, "/logout", json!("None"), json!("None")),
+            route(HttpMethod::Post, "/task", task.clone(), task.clone()),
+            route(HttpMethod::Get, "/task/{id}", json!("None"), task),
+        ];
+
+        let rendered = render_api_tests(&routes, true, "web_template");
+        let crud_task = &rendered[rendered.find("fn crud_task() {").unwrap()..];
+
+        assert!(rendered.contains(
+            r#"const TOKEN_FIELDS: [&str; 3] = ["token", "access_token", "accessToken"];"#
+        ));
+        assert!(rendered.contains("fn auth_flow() {"));
+        assert!(crud_task
+            .contains("    let mut session = Session::new();\n    let suffix = unique_suffix();"));
+        assert!(crud_task.contains("    let body = with_suffix(parse("));
+        assert!(crud_task.contains("    // POST /login\n"));
+        assert!(!crud_task.contains("    // POST /logout\n"));
+        assert!(crud_task.contains("    session.keep_token(&response);"));
+        assert!(
+            crud_task.find("    // POST /login\n").unwrap()
+                < crud_task.find("    // POST /task\n").unwrap()
+        );
+        assert!(syn::parse_file(&rendered).is_ok());
+    }
+}
@@ -0,0 +1,381 @@
+// import
+use reqwest::Client;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::helper::endpoints::{fill_path, EndpointCall, TestFlow};
+use crate::models::agent::pro::pro_trait::RouteObject;
+use crate::models::agent::pro::route_schema::HttpMethod;
+
+// const
+const DEFAULT_CONCURRENCY: usize = 8;
+const DEFAULT_DURATION_SECS: u64 = 10;
+const REQUEST_TIMEOUT_SECS: u64 = 5;
+pub const BENCH_REPORT_FILE: &str = "bench.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchConfig {
+    pub concurrency: usize,
+    pub duration: Duration,
+    // Bench a server that is already running instead of launching one
+    pub base_url: Option<String>,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            duration: Duration::from_secs(DEFAULT_DURATION_SECS),
+            base_url: None,
+        }
+    }
+}
+
+impl BenchConfig {
+    // --concurrency <n> --duration <secs> --url <base url>, any order
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut args = args.iter();
+
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", flag))?;
+            match flag.as_str() {
+                "--concurrency" | "-c" => {
+                    config.concurrency = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|concurrency| *concurrency > 0)
+                        .ok_or_else(|| format!("Invalid concurrency: {}", value))?;
+                }
+                "--duration" | "-d" => {
+                    let secs = value
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|secs| *secs > 0)
+                        .ok_or_else(|| format!("Invalid duration: {}", value))?;
+                    config.duration = Duration::from_secs(secs);
+                }
+                "--url" => config.base_url = Some(value.trim_end_matches('/').to_string()),
+                _ => return Err(format!("Unknown bench option: {}", flag)),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointBench {
+    pub route: String,
+    pub url: String,
+    pub requests: usize,
+    pub errors: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub throughput_rps: f64,
+    pub error_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedEndpoint {
+    pub route: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub base_url: String,
+    pub concurrency: usize,
+    pub duration_secs: u64,
+    pub endpoints: Vec<EndpointBench>,
+    pub skipped: Vec<SkippedEndpoint>,
+}
+
+// Nearest rank percentile of sorted latencies, in milliseconds
+pub fn percentile(sorted: &[Duration], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = ((percent / 100.0) * sorted.len() as f64).ceil() as usize;
+    let index = rank.clamp(1, sorted.len()) - 1;
+    sorted[index].as_secs_f64() * 1000.0
+}
+
+// Every GET route with its path filled the same way the endpoint tests do
+pub fn bench_targets(routes: &[RouteObject]) -> Vec<(String, String)> {
+    let no_ids: HashMap<String, serde_json::Value> = HashMap::new();
+
+    routes
+        .iter()
+        .filter(|route| route.method == HttpMethod::Get)
+        .map(|route| {
+            let call = EndpointCall {
+                route: route.clone(),
+                flow: TestFlow::Single,
+                body: None,
+                captures_id: false,
+            };
+            (route.route.clone(), fill_path(&call, &no_ids))
+        })
+        .collect()
+}
+
+// Hammer one url from `concurrency` workers until the duration is up
+pub async fn bench_endpoint(
+    client: &Client,
+    route: &str,
+    url: &str,
+    config: &BenchConfig,
+) -> EndpointBench {
+    let started = Instant::now();
+    let deadline = started + config.duration;
+    let url = Arc::new(url.to_string());
+
+    let workers: Vec<_> = (0..config.concurrency)
+        .map(|_| {
+            let client = client.clone();
+            let url = url.clone();
+            tokio::spawn(async move {
+                let mut latencies: Vec<Duration> = vec![];
+                let mut errors = 0;
+                while Instant::now() < deadline {
+                    let sent = Instant::now();
+                    let is_ok = match client.get(url.as_str()).send().await {
+                        Ok(response) => {
+                            let is_success = response.status().is_success();
+                            // Read the body so the latency covers the whole response
+                            response.bytes().await.is_ok() && is_success
+                        }
+                        Err(_) => false,
+                    };
+                    latencies.push(sent.elapsed());
+                    if !is_ok {
+                        errors += 1;
+                    }
+                }
+                (latencies, errors)
+            })
+        })
+        .collect();
+
+    let mut latencies: Vec<Duration> = vec![];
+    let mut errors = 0;
+    for worker in workers {
+        if let Ok((worker_latencies, worker_errors)) = worker.await {
+            latencies.extend(worker_latencies);
+            errors += worker_errors;
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+    latencies.sort();
+
+    let requests = latencies.len();
+    EndpointBench {
+        route: route.to_string(),
+        url: url.to_string(),
+        requests,
+        errors,
+        p50_ms: percentile(&latencies, 50.0),
+        p95_ms: percentile(&latencies, 95.0),
+        p99_ms: percentile(&latencies, 99.0),
+        throughput_rps: if elapsed > 0.0 {
+            requests as f64 / elapsed
+        } else {
+            0.0
+        },
+        error_rate: if requests > 0 {
+            errors as f64 / requests as f64
+        } else {
+            0.0
+        },
+    }
+}
+
+// Only bench GET routes that answer 2xx once, the others are reported as skipped
+pub async fn run_bench(
+    base_url: &str,
+    routes: &[RouteObject],
+    config: &BenchConfig,
+) -> BenchReport {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .expect("Failed to build bench client");
+    let mut report = BenchReport {
+        base_url: base_url.to_string(),
+        concurrency: config.concurrency,
+        duration_secs: config.duration.as_secs(),
+        endpoints: vec![],
+        skipped: vec![],
+    };
+
+    for (route, path) in bench_targets(routes) {
+        let url = format!("{}{}", base_url.trim_end_matches('/'), path);
+
+        let reason = match client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => None,
+            Ok(response) => Some(format!("returned status {}", response.status().as_u16())),
+            Err(e) => Some(format!("request failed: {}", e)),
+        };
+        match reason {
+            Some(reason) => report.skipped.push(SkippedEndpoint { route, reason }),
+            None => report
+                .endpoints
+                .push(bench_endpoint(&client, &route, &url, config).await),
+        }
+    }
+
+    report
+}
+
+pub fn save_bench_report(run_dir: &Path, report: &BenchReport) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(run_dir)?;
+    let path = run_dir.join(BENCH_REPORT_FILE);
+    let json = serde_json::to_string_pretty(report).expect("Failed to encode bench report");
+    fs::write(&path, json)?;
+    Ok(path)
+}
+
+pub fn print_bench_report(report: &BenchReport) {
+    println!(
+        "Bench {} with {} workers for {}s",
+        report.base_url, report.concurrency, report.duration_secs
+    );
+    println!(
+        "{:<32} {:>9} {:>9} {:>9} {:>9} {:>10} {:>7}",
+        "ROUTE", "REQUESTS", "P50 ms", "P95 ms", "P99 ms", "REQ/S", "ERR %"
+    );
+    for endpoint in &report.endpoints {
+        println!(
+            "{:<32} {:>9} {:>9.2} {:>9.2} {:>9.2} {:>10.1} {:>7.2}",
+            endpoint.route,
+            endpoint.requests,
+            endpoint.p50_ms,
+            endpoint.p95_ms,
+            endpoint.p99_ms,
+            endpoint.throughput_rps,
+            endpoint.error_rate * 100.0
+        );
+    }
+    for skipped in &report.skipped {
+        println!("SKIP {}: {}", skipped.route, skipped.reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agent::pro::route_schema::BodySchema;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    fn route(method: HttpMethod, path: &str) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: path.contains('{'),
+            method,
+            request_body: BodySchema::None,
+            response: BodySchema::None,
+            route: String::from(path),
+            handler: None,
+            json_body_type: None,
+            path_params_type: path.contains('{').then(|| String::from("u64")),
+            requirement_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn test_bench_config_from_args() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(BenchConfig::from_args(&[]), Ok(BenchConfig::default()));
+        assert_eq!(
+            BenchConfig::from_args(&args(&[
+                "-c",
+                "4",
+                "--duration",
+                "3",
+                "--url",
+                "http://127.0.0.1:9000/"
+            ])),
+            Ok(BenchConfig {
+                concurrency: 4,
+                duration: Duration::from_secs(3),
+                base_url: Some(String::from("http://127.0.0.1:9000")),
+            })
+        );
+        assert!(BenchConfig::from_args(&args(&["--concurrency", "0"])).is_err());
+        assert!(BenchConfig::from_args(&args(&["--duration"])).is_err());
+        assert!(BenchConfig::from_args(&args(&["--rate", "5"])).is_err());
+    }
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+
+        assert_eq!(percentile(&latencies, 50.0), 50.0);
+        assert_eq!(percentile(&latencies, 95.0), 95.0);
+        assert_eq!(percentile(&latencies, 99.0), 99.0);
+        assert_eq!(percentile(&[], 99.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_run_bench() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                tokio::spawn(async move {
+                    let mut buffer = [0u8; 1024];
+                    let read = socket.read(&mut buffer).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buffer[..read]);
+                    let status = if request.starts_with("GET /task/1 ") {
+                        "200 OK"
+                    } else {
+                        "404 Not Found"
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\ncontent-length: 2\r\nconnection: close\r\n\r\n[]",
+                        status
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        let routes = vec![
+            route(HttpMethod::Get, "/task/{id}"),
+            route(HttpMethod::Get, "/missing"),
+            route(HttpMethod::Post, "/task"),
+        ];
+        let config = BenchConfig {
+            concurrency: 2,
+            duration: Duration::from_millis(300),
+            base_url: None,
+        };
+
+        let report = run_bench(&format!("http://{}", address), &routes, &config).await;
+
+        assert_eq!(report.endpoints.len(), 1);
+        assert_eq!(report.endpoints[0].route, "/task/{id}");
+        assert!(report.endpoints[0].requests > 0);
+        assert_eq!(report.endpoints[0].errors, 0);
+        assert!(report.endpoints[0].p50_ms <= report.endpoints[0].p99_ms);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, "returned status 404");
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     io::{stdin, stdout},
+    path::PathBuf,
     process::Command,
 };
 
@@ -8,7 +9,49 @@ use crossterm::{
     ExecutableCommand,
 };
 
-use crate::{helper::general::EXEC_MAIN_PATH, utils::command_color::CommandColor};
+use crate::{
+    helper::{bench::BenchConfig, general::WEB_SERVER_PROJECT_PATH},
+    utils::command_color::CommandColor,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum PlottoCommand {
+    // Requirements file given with --spec
+    New(Option<PathBuf>),
+    Rollback(String),
+    Bench(BenchConfig),
+}
+
+impl PlottoCommand {
+    // plotto [new [--spec file]] | plotto rollback <rev> | plotto bench [--concurrency n] [--duration secs] [--url url]
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        match args.get(1).map(String::as_str) {
+            None => Ok(PlottoCommand::New(None)),
+            Some("new") => match &args[2..] {
+                [] => Ok(PlottoCommand::New(None)),
+                [flag, path] if flag == "--spec" => {
+                    Ok(PlottoCommand::New(Some(PathBuf::from(path))))
+                }
+                _ => Err(String::from(
+                    "Usage: plotto new [--spec requirements.md|requirements.yaml]",
+                )),
+            },
+            Some("bench") => BenchConfig::from_args(&args[2..])
+                .map(PlottoCommand::Bench)
+                .map_err(|error| {
+                    format!(
+                        "{}\nUsage: plotto bench [--concurrency n] [--duration secs] [--url url]",
+                        error
+                    )
+                }),
+            Some("rollback") => match args.get(2) {
+                Some(rev) => Ok(PlottoCommand::Rollback(rev.clone())),
+                None => Err(String::from("Usage: plotto rollback <rev>")),
+            },
+            Some(command) => Err(format!("Unknown command: {}", command)),
+        }
+    }
+}
 
 pub enum AgentCommand {
     Info,
@@ -45,7 +88,7 @@ pub fn confirm_safe_code() -> bool {
     loop {
         // Open project generated
         Command::new("code")
-            .arg(EXEC_MAIN_PATH)
+            .arg(WEB_SERVER_PROJECT_PATH)
             .output()
             .expect("Something went wrong to open source code generated!");
 
@@ -126,6 +169,34 @@ mod tests {
         println!("");
     }
 
+    #[test]
+    fn test_plotto_command_from_args() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            PlottoCommand::from_args(&args(&["plotto"])),
+            Ok(PlottoCommand::New(None))
+        );
+        assert_eq!(
+            PlottoCommand::from_args(&args(&["plotto", "new", "--spec", "requirements.md"])),
+            Ok(PlottoCommand::New(Some(PathBuf::from("requirements.md"))))
+        );
+        assert!(PlottoCommand::from_args(&args(&["plotto", "new", "--spec"])).is_err());
+        assert_eq!(
+            PlottoCommand::from_args(&args(&["plotto", "rollback", "a1b2c3d"])),
+            Ok(PlottoCommand::Rollback(String::from("a1b2c3d")))
+        );
+        assert!(PlottoCommand::from_args(&args(&["plotto", "rollback"])).is_err());
+        assert_eq!(
+            PlottoCommand::from_args(&args(&["plotto", "bench", "--concurrency", "2"])),
+            Ok(PlottoCommand::Bench(BenchConfig {
+                concurrency: 2,
+                ..BenchConfig::default()
+            }))
+        );
+        assert!(PlottoCommand::from_args(&args(&["plotto", "deploy"])).is_err());
+    }
+
     #[test]
     fn test_confirm_safe_code() {
         confirm_safe_code();
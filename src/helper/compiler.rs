@@ -0,0 +1,478 @@
+// import
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+// const
+const MACHINE_APPLICABLE: &str = "MachineApplicable";
+const DEFAULT_STAGE_RETRIES: u8 = 2;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DiagnosticSpan {
+    pub file_name: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub suggested_replacement: Option<String>,
+    pub suggestion_applicability: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    pub level: String,
+    pub spans: Vec<DiagnosticSpan>,
+    pub children: Vec<Diagnostic>,
+    pub rendered: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<Diagnostic>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuggestedEdit {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QualityStage {
+    Check,
+    Build,
+    Clippy,
+    Test,
+}
+
+impl QualityStage {
+    pub const ALL: [QualityStage; 4] = [
+        QualityStage::Check,
+        QualityStage::Build,
+        QualityStage::Clippy,
+        QualityStage::Test,
+    ];
+
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword.trim().to_lowercase().as_str() {
+            "check" => Some(QualityStage::Check),
+            "build" => Some(QualityStage::Build),
+            "clippy" => Some(QualityStage::Clippy),
+            "test" => Some(QualityStage::Test),
+            _ => None,
+        }
+    }
+
+    pub fn get_args(&self) -> Vec<&'static str> {
+        match self {
+            QualityStage::Check => vec!["check", "--message-format=json"],
+            QualityStage::Build => vec!["build", "--message-format=json"],
+            QualityStage::Clippy => {
+                vec!["clippy", "--message-format=json", "--", "-D", "warnings"]
+            }
+            QualityStage::Test => vec!["test", "--message-format=json"],
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            QualityStage::Check => "cargo check",
+            QualityStage::Build => "cargo build",
+            QualityStage::Clippy => "cargo clippy -D warnings",
+            QualityStage::Test => "cargo test",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StageBudget {
+    pub stage: QualityStage,
+    pub max_retries: u8,
+}
+
+// Ordered stages the generated code must pass, each with its own retry budget
+#[derive(Debug, Clone)]
+pub struct QualityGate {
+    pub stages: Vec<StageBudget>,
+}
+
+impl Default for QualityGate {
+    fn default() -> Self {
+        Self::with_stages(&QualityStage::ALL)
+    }
+}
+
+impl QualityGate {
+    fn with_stages(stages: &[QualityStage]) -> Self {
+        let stages = stages
+            .iter()
+            .map(|stage| StageBudget {
+                stage: *stage,
+                max_retries: DEFAULT_STAGE_RETRIES,
+            })
+            .collect();
+
+        Self { stages }
+    }
+
+    // PLOTTO_GATE_STAGES=check,build,test picks and orders the stages,
+    // PLOTTO_GATE_RETRIES=3 sets every budget and clippy=1,test=3 sets single ones
+    pub fn from_env() -> Self {
+        Self::from_settings(
+            env::var("PLOTTO_GATE_STAGES").ok().as_deref(),
+            env::var("PLOTTO_GATE_RETRIES").ok().as_deref(),
+        )
+    }
+
+    // Unknown stages keep the default stages, unreadable budgets are skipped
+    pub fn from_settings(stages: Option<&str>, retries: Option<&str>) -> Self {
+        let mut gate = match stages.and_then(parse_stages) {
+            Some(stages) => Self::with_stages(&stages),
+            None => Self::default(),
+        };
+
+        for entry in retries.unwrap_or_default().split(',').map(str::trim) {
+            match entry.split_once('=') {
+                Some((stage, count)) => {
+                    let stage = QualityStage::from_keyword(stage);
+                    if let (Some(stage), Ok(count)) = (stage, count.trim().parse::<u8>()) {
+                        gate.stages
+                            .iter_mut()
+                            .filter(|budget| budget.stage == stage)
+                            .for_each(|budget| budget.max_retries = count);
+                    }
+                }
+                None => {
+                    if let Ok(count) = entry.parse::<u8>() {
+                        gate.stages
+                            .iter_mut()
+                            .for_each(|budget| budget.max_retries = count);
+                    }
+                }
+            }
+        }
+
+        gate
+    }
+}
+
+fn parse_stages(text: &str) -> Option<Vec<QualityStage>> {
+    let mut stages: Vec<QualityStage> = vec![];
+    for keyword in text.split(',').filter(|keyword| !keyword.trim().is_empty()) {
+        let stage = QualityStage::from_keyword(keyword)?;
+        if !stages.contains(&stage) {
+            stages.push(stage);
+        }
+    }
+
+    (!stages.is_empty()).then_some(stages)
+}
+
+#[derive(Debug)]
+pub struct BuildOutput {
+    pub success: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    pub output: String,
+    pub stderr: String,
+}
+
+impl BuildOutput {
+    pub fn errors(&self) -> Vec<&Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == "error")
+            .collect()
+    }
+
+    // Rendered compiler errors, falling back to raw output for test failures or cargo errors
+    pub fn error_report(&self) -> String {
+        let rendered: Vec<String> = self
+            .errors()
+            .iter()
+            .map(|diagnostic| {
+                diagnostic
+                    .rendered
+                    .clone()
+                    .unwrap_or_else(|| diagnostic.message.clone())
+            })
+            .collect();
+
+        if rendered.is_empty() {
+            format!("{}\n{}", self.output, self.stderr)
+                .trim()
+                .to_string()
+        } else {
+            rendered.join("\n")
+        }
+    }
+}
+
+// Run a quality stage on the project and collect structured diagnostics from rustc
+pub fn run_stage(project_path: &str, stage: QualityStage) -> BuildOutput {
+    let output = Command::new("cargo")
+        .args(stage.get_args())
+        .current_dir(project_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("Failed to run cargo on backend application");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    BuildOutput {
+        success: output.status.success(),
+        diagnostics: parse_build_messages(&stdout),
+        output: parse_plain_output(&stdout),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    }
+}
+
+// Parse `cargo --message-format=json` output into compiler diagnostics
+pub fn parse_build_messages(stdout: &str) -> Vec<Diagnostic> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|cargo_message| cargo_message.reason == "compiler-message")
+        .filter_map(|cargo_message| cargo_message.message)
+        .collect()
+}
+
+// Keep the lines that are not cargo json messages, e.g. the test harness output
+pub fn parse_plain_output(stdout: &str) -> String {
+    stdout
+        .lines()
+        .filter(|line| serde_json::from_str::<CargoMessage>(line).is_err())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+// Group every machine-applicable suggestion by the file it targets
+pub fn machine_applicable_edits(diagnostics: &[Diagnostic]) -> HashMap<String, Vec<SuggestedEdit>> {
+    let mut edits: HashMap<String, Vec<SuggestedEdit>> = HashMap::new();
+
+    fn collect(diagnostic: &Diagnostic, edits: &mut HashMap<String, Vec<SuggestedEdit>>) {
+        for span in &diagnostic.spans {
+            let is_machine_applicable =
+                span.suggestion_applicability.as_deref() == Some(MACHINE_APPLICABLE);
+
+            if let (true, Some(replacement)) = (is_machine_applicable, &span.suggested_replacement)
+            {
+                let edit = SuggestedEdit {
+                    byte_start: span.byte_start,
+                    byte_end: span.byte_end,
+                    replacement: replacement.clone(),
+                };
+
+                // Cargo can report the same suggestion more than once
+                let file_edits = edits.entry(span.file_name.clone()).or_default();
+                if !file_edits.contains(&edit) {
+                    file_edits.push(edit);
+                }
+            }
+        }
+
+        for child in &diagnostic.children {
+            collect(child, edits);
+        }
+    }
+
+    for diagnostic in diagnostics {
+        collect(diagnostic, &mut edits);
+    }
+
+    edits
+}
+
+// Apply edits back to front so earlier byte offsets stay valid, skipping overlaps
+pub fn apply_edits(source: &str, edits: &[SuggestedEdit]) -> (String, usize) {
+    let mut sorted: Vec<&SuggestedEdit> = edits.iter().collect();
+    sorted.sort_by_key(|edit| std::cmp::Reverse(edit.byte_start));
+
+    let mut fixed = source.to_string();
+    let mut applied: usize = 0;
+    let mut lower_bound = usize::MAX;
+
+    for edit in sorted {
+        let in_bounds = edit.byte_start <= edit.byte_end && edit.byte_end <= fixed.len();
+        if !in_bounds
+            || edit.byte_end > lower_bound
+            || !fixed.is_char_boundary(edit.byte_start)
+            || !fixed.is_char_boundary(edit.byte_end)
+        {
+            continue;
+        }
+
+        fixed.replace_range(edit.byte_start..edit.byte_end, &edit.replacement);
+        lower_bound = edit.byte_start;
+        applied += 1;
+    }
+
+    (fixed, applied)
+}
+
+// Write machine-applicable suggestions into the project, returns number of edits applied
+pub fn apply_machine_applicable_fixes(project_path: &str, diagnostics: &[Diagnostic]) -> usize {
+    let mut applied: usize = 0;
+
+    for (file_name, edits) in machine_applicable_edits(diagnostics) {
+        let path = Path::new(project_path).join(&file_name);
+
+        // Never touch files outside the generated project (e.g. registry sources)
+        if !path.starts_with(project_path) || file_name.contains("..") {
+            continue;
+        }
+
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let (fixed, count) = apply_edits(&source, &edits);
+        if count > 0 {
+            fs::write(&path, fixed).expect("Failed to write compiler suggestions");
+            applied += count;
+        }
+    }
+
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUILD_MESSAGES: &str = r#"{"reason":"compiler-artifact","package_id":"serde"}
+{"reason":"compiler-message","message":{"message":"variable does not need to be mutable","level":"warning","spans":[{"file_name":"src/main.rs","byte_start":16,"byte_end":20,"suggested_replacement":null,"suggestion_applicability":null}],"children":[{"message":"remove this `mut`","level":"help","spans":[{"file_name":"src/main.rs","byte_start":16,"byte_end":20,"suggested_replacement":"","suggestion_applicability":"MachineApplicable"}],"children":[],"rendered":null}],"rendered":"warning: variable does not need to be mutable"}}
+{"reason":"compiler-message","message":{"message":"cannot find type `HashMap` in this scope","level":"error","spans":[],"children":[{"message":"consider importing this struct","level":"help","spans":[{"file_name":"src/main.rs","byte_start":0,"byte_end":0,"suggested_replacement":"use std::collections::HashMap;\n","suggestion_applicability":"MachineApplicable"}],"children":[],"rendered":null}],"rendered":"error[E0412]: cannot find type `HashMap` in this scope"}}
+{"reason":"build-finished","success":false}"#;
+
+    #[test]
+    fn test_parse_build_messages() {
+        let diagnostics = parse_build_messages(BUILD_MESSAGES);
+        assert_eq!(diagnostics.len(), 2);
+
+        let build_output = BuildOutput {
+            success: false,
+            diagnostics,
+            output: String::new(),
+            stderr: String::new(),
+        };
+        assert_eq!(build_output.errors().len(), 1);
+        assert!(build_output.error_report().contains("E0412"));
+    }
+
+    #[test]
+    fn test_error_report_from_test_output() {
+        let stdout = format!(
+            "{}\nrunning 1 test\ntest tests::it_works ... FAILED\n",
+            r#"{"reason":"compiler-artifact","package_id":"web_template"}"#
+        );
+
+        let build_output = BuildOutput {
+            success: false,
+            diagnostics: parse_build_messages(&stdout),
+            output: parse_plain_output(&stdout),
+            stderr: String::from("error: test failed, to rerun pass `--bin web_template`"),
+        };
+
+        let error_report = build_output.error_report();
+        assert!(error_report.starts_with("running 1 test"));
+        assert!(error_report.contains("it_works ... FAILED"));
+        assert!(error_report.ends_with("`--bin web_template`"));
+    }
+
+    #[test]
+    fn test_quality_gate_from_settings() {
+        let budgets = |gate: QualityGate| -> Vec<(QualityStage, u8)> {
+            gate.stages
+                .iter()
+                .map(|budget| (budget.stage, budget.max_retries))
+                .collect()
+        };
+
+        assert_eq!(
+            budgets(QualityGate::from_settings(
+                Some("Build, test,build"),
+                Some("3,test=1")
+            )),
+            vec![(QualityStage::Build, 3), (QualityStage::Test, 1)]
+        );
+        assert_eq!(
+            budgets(QualityGate::from_settings(
+                Some("check,lint"),
+                Some("clippy=0,many")
+            )),
+            vec![
+                (QualityStage::Check, 2),
+                (QualityStage::Build, 2),
+                (QualityStage::Clippy, 0),
+                (QualityStage::Test, 2)
+            ]
+        );
+        assert_eq!(
+            budgets(QualityGate::from_settings(None, None)),
+            budgets(QualityGate::default())
+        );
+    }
+
+    #[test]
+    fn test_default_quality_gate_order() {
+        let stages: Vec<QualityStage> = QualityGate::default()
+            .stages
+            .iter()
+            .map(|budget| budget.stage)
+            .collect();
+
+        assert_eq!(
+            stages,
+            vec![
+                QualityStage::Check,
+                QualityStage::Build,
+                QualityStage::Clippy,
+                QualityStage::Test
+            ]
+        );
+        assert_eq!(
+            QualityStage::Clippy.get_args(),
+            vec!["clippy", "--message-format=json", "--", "-D", "warnings"]
+        );
+    }
+
+    #[test]
+    fn test_apply_machine_applicable_edits() {
+        let source = "fn main() { let mut map: HashMap<u8, u8> = HashMap::new(); }";
+        let diagnostics = parse_build_messages(BUILD_MESSAGES);
+        let edits = machine_applicable_edits(&diagnostics);
+
+        let (fixed, applied) = apply_edits(source, &edits["src/main.rs"]);
+        assert_eq!(applied, 2);
+        assert_eq!(
+            fixed,
+            "use std::collections::HashMap;\nfn main() { let map: HashMap<u8, u8> = HashMap::new(); }"
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_skips_overlaps() {
+        let edits = vec![
+            SuggestedEdit {
+                byte_start: 0,
+                byte_end: 5,
+                replacement: String::from("hi"),
+            },
+            SuggestedEdit {
+                byte_start: 3,
+                byte_end: 8,
+                replacement: String::from("there"),
+            },
+        ];
+
+        let (fixed, applied) = apply_edits("hello world", &edits);
+        assert_eq!(applied, 1);
+        assert_eq!(fixed, "helthererld");
+    }
+}
@@ -0,0 +1,696 @@
+// import
+use reqwest::{Client, Method};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::helper::openapi::path_template;
+use crate::models::agent::pro::pro_trait::RouteObject;
+use crate::models::agent::pro::route_schema::{BodySchema, HttpMethod, PrimitiveType};
+
+// const
+const REGISTER_KEYWORDS: [&str; 4] = ["register", "signup", "sign_up", "sign-up"];
+const LOGIN_KEYWORDS: [&str; 4] = ["login", "signin", "sign_in", "sign-in"];
+const LOGOUT_KEYWORDS: [&str; 4] = ["logout", "signout", "sign_out", "sign-out"];
+pub const TOKEN_FIELDS: [&str; 3] = ["token", "access_token", "accessToken"];
+const MAX_REPORTED_BODY_CHARS: usize = 2000;
+const INTEGER_TYPES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestFlow {
+    Auth,
+    Crud(String),
+    Single,
+}
+
+#[derive(Debug, Clone)]
+pub struct EndpointCall {
+    pub route: RouteObject,
+    pub flow: TestFlow,
+    pub body: Option<Value>,
+    // The create call of a CRUD resource remembers the id for the next calls
+    pub captures_id: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct EndpointResult {
+    pub method: HttpMethod,
+    pub route: String,
+    pub url: String,
+    pub status: Option<u16>,
+    pub passed: bool,
+    pub reason: Option<String>,
+    pub request_body: Option<Value>,
+    pub response_body: Option<String>,
+    pub schema_errors: Vec<String>,
+}
+
+// Order every route into auth, CRUD and single call sequences
+pub fn plan_endpoint_tests(routes: &[RouteObject], is_login_required: bool) -> Vec<EndpointCall> {
+    let mut calls: Vec<EndpointCall> = vec![];
+    let mut planned: HashSet<usize> = HashSet::new();
+
+    let (auth_calls, logout_call) = if is_login_required {
+        plan_auth_flow(routes, &mut planned)
+    } else {
+        (vec![], None)
+    };
+    calls.extend(auth_calls);
+
+    // Resources in the order they first appear
+    let mut resources: Vec<String> = vec![];
+    for route in routes {
+        let resource = resource_of(&route.route);
+        if !resources.contains(&resource) {
+            resources.push(resource);
+        }
+    }
+    for resource in resources {
+        calls.extend(plan_crud_flow(routes, &resource, &mut planned));
+    }
+
+    for (index, route) in routes.iter().enumerate() {
+        if planned.contains(&index) {
+            continue;
+        }
+        calls.push(EndpointCall {
+            route: route.clone(),
+            flow: TestFlow::Single,
+            body: request_body(route),
+            captures_id: false,
+        });
+    }
+
+    // Logout ends the session only after everything else ran in it
+    calls.extend(logout_call);
+
+    calls
+}
+
+pub fn is_logout_route(route: &str) -> bool {
+    let path = route.to_lowercase();
+    LOGOUT_KEYWORDS.iter().any(|keyword| path.contains(keyword))
+}
+
+// register -> login with the same credentials, and the logout call kept apart
+fn plan_auth_flow(
+    routes: &[RouteObject],
+    planned: &mut HashSet<usize>,
+) -> (Vec<EndpointCall>, Option<EndpointCall>) {
+    let find = |keywords: &[&str]| {
+        routes.iter().position(|route| {
+            let path = route.route.to_lowercase();
+            keywords.iter().any(|keyword| path.contains(keyword))
+        })
+    };
+
+    let mut calls: Vec<EndpointCall> = vec![];
+    let mut credentials = Map::new();
+    let logout = find(&LOGOUT_KEYWORDS);
+
+    for index in [find(&REGISTER_KEYWORDS), find(&LOGIN_KEYWORDS), logout]
+        .into_iter()
+        .flatten()
+    {
+        let route = &routes[index];
+        let mut body = request_body(route);
+
+        // Login reuses whatever register sent for the same field names
+        if let Some(Value::Object(fields)) = body.as_mut() {
+            for (name, value) in fields.iter_mut() {
+                match credentials.get(name) {
+                    Some(known) => *value = known.clone(),
+                    None => {
+                        credentials.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        planned.insert(index);
+        calls.push(EndpointCall {
+            route: route.clone(),
+            flow: TestFlow::Auth,
+            body,
+            captures_id: false,
+        });
+    }
+
+    let logout_call = logout.and_then(|_| calls.pop());
+    (calls, logout_call)
+}
+
+// create -> list -> read -> update -> delete, when the resource can be created
+fn plan_crud_flow(
+    routes: &[RouteObject],
+    resource: &str,
+    planned: &mut HashSet<usize>,
+) -> Vec<EndpointCall> {
+    let find = |methods: &[HttpMethod], is_item: Option<bool>| {
+        routes.iter().enumerate().position(|(index, route)| {
+            !planned.contains(&index)
+                && methods.contains(&route.method)
+                && resource_of(&route.route) == resource
+                && is_item.is_none_or(|is_item| is_item_route(&route.route) == is_item)
+        })
+    };
+
+    let Some(create) = find(&[HttpMethod::Post], Some(false)) else {
+        return vec![];
+    };
+    let steps = [
+        Some(create),
+        find(&[HttpMethod::Get], Some(false)),
+        find(&[HttpMethod::Get], Some(true)),
+        find(&[HttpMethod::Put, HttpMethod::Patch], None),
+        find(&[HttpMethod::Delete], None),
+    ];
+
+    // Only a sequence when something reads or changes what was created
+    if steps.iter().skip(1).all(Option::is_none) {
+        return vec![];
+    }
+
+    steps
+        .into_iter()
+        .flatten()
+        .map(|index| {
+            planned.insert(index);
+            let route = &routes[index];
+            EndpointCall {
+                route: route.clone(),
+                flow: TestFlow::Crud(resource.to_string()),
+                body: request_body(route),
+                captures_id: index == create,
+            }
+        })
+        .collect()
+}
+
+// "/api/task/{id}" -> "/api/task"
+fn resource_of(route: &str) -> String {
+    let trimmed = route.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some((base, last)) if last.starts_with('{') => base.to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+fn is_item_route(route: &str) -> bool {
+    route
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .is_some_and(|last| last.starts_with('{'))
+}
+
+fn request_body(route: &RouteObject) -> Option<Value> {
+    match &route.request_body {
+        BodySchema::None => None,
+        schema => Some(synthesize_value(schema, "")),
+    }
+}
+
+// Plausible JSON for a schema, field names pick the flavour of strings
+pub fn synthesize_value(schema: &BodySchema, field_name: &str) -> Value {
+    match schema {
+        BodySchema::None => Value::Null,
+        BodySchema::NotProvided => json!({}),
+        BodySchema::Primitive(primitive) => match primitive {
+            PrimitiveType::Number => json!(1),
+            PrimitiveType::Bool => json!(true),
+            PrimitiveType::Object => json!({}),
+            PrimitiveType::Array => json!([]),
+            PrimitiveType::String | PrimitiveType::Any => {
+                let name = field_name.to_lowercase();
+                if name.contains("email") {
+                    json!("plotto@example.com")
+                } else if name.contains("password") {
+                    json!("Plotto-Test-123")
+                } else if name.is_empty() {
+                    json!("plotto")
+                } else {
+                    json!(format!("plotto_{}", name))
+                }
+            }
+        },
+        BodySchema::Array(item) => json!([synthesize_value(item, field_name)]),
+        BodySchema::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, field)| (name.clone(), synthesize_value(&field.schema, name)))
+                .collect(),
+        ),
+    }
+}
+
+// Fill path parameters: the created id for the resource, otherwise a value of the right type
+pub fn fill_path(call: &EndpointCall, captured_ids: &HashMap<String, Value>) -> String {
+    let (template, names) = path_template(&call.route.route);
+    let types: Vec<String> = call
+        .route
+        .path_params_type
+        .as_deref()
+        .unwrap_or_default()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|ty| ty.trim().to_string())
+        .collect();
+    let captured = match &call.flow {
+        TestFlow::Crud(resource) => captured_ids.get(resource),
+        _ => None,
+    };
+
+    let mut path = template;
+    for (index, name) in names.iter().enumerate() {
+        let value = match (captured, index + 1 == names.len()) {
+            (Some(Value::String(id)), true) => id.clone(),
+            (Some(id), true) => id.to_string(),
+            _ if types
+                .get(index)
+                .is_some_and(|ty| INTEGER_TYPES.contains(&ty.as_str())) =>
+            {
+                String::from("1")
+            }
+            _ => String::from("plotto"),
+        };
+        path = path.replacen(&format!("{{{}}}", name), &value, 1);
+    }
+
+    path
+}
+
+// The id the server returned, or the one we sent
+fn find_id(response: &Value, body: Option<&Value>) -> Option<Value> {
+    let from_object = |value: &Value| {
+        let object = value.as_object()?;
+        object.get("id").cloned().or_else(|| {
+            object
+                .iter()
+                .find(|(key, value)| key.ends_with("_id") && !value.is_null())
+                .map(|(_, value)| value.clone())
+        })
+    };
+
+    from_object(response).or_else(|| body.and_then(from_object))
+}
+
+// A bearer token in the login response: {"token": ".."} or {"data": {"access_token": ".."}}
+fn find_token(response: &Value) -> Option<String> {
+    let object = response.as_object()?;
+    TOKEN_FIELDS
+        .iter()
+        .find_map(|field| object.get(*field)?.as_str().map(String::from))
+        .or_else(|| object.values().find_map(find_token))
+}
+
+// Run every planned call against the live server, one result per call.
+// Cookies set by login stay in the client's cookie store, a returned token is sent as Bearer
+pub async fn run_endpoint_tests(
+    client: &Client,
+    base_url: &str,
+    calls: &[EndpointCall],
+) -> Vec<EndpointResult> {
+    let mut results: Vec<EndpointResult> = vec![];
+    let mut captured_ids: HashMap<String, Value> = HashMap::new();
+    let mut token: Option<String> = None;
+
+    for call in calls {
+        let url = format!(
+            "{}{}",
+            base_url.trim_end_matches('/'),
+            fill_path(call, &captured_ids)
+        );
+
+        // Later CRUD calls send the id of the created record
+        let mut body = call.body.clone();
+        if let (TestFlow::Crud(resource), Some(Value::Object(fields))) = (&call.flow, body.as_mut())
+        {
+            if let (Some(id), Some(field)) = (captured_ids.get(resource), fields.get_mut("id")) {
+                *field = id.clone();
+            }
+        }
+
+        let method = Method::from_bytes(call.route.method.as_str().to_uppercase().as_bytes())
+            .unwrap_or(Method::GET);
+        let mut request = client.request(method, &url);
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+
+        let mut result = EndpointResult {
+            method: call.route.method,
+            route: call.route.route.clone(),
+            url,
+            status: None,
+            passed: false,
+            reason: None,
+            request_body: body.clone(),
+            response_body: None,
+            schema_errors: vec![],
+        };
+
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                let response_json: Option<Value> = serde_json::from_str(&text).ok();
+                result.status = Some(status.as_u16());
+                result.response_body = Some(text);
+
+                if !status.is_success() {
+                    result.reason = Some(format!("returned status {}", status.as_u16()));
+                    results.push(result);
+                    continue;
+                }
+
+                if let (TestFlow::Auth, Some(found)) =
+                    (&call.flow, response_json.as_ref().and_then(find_token))
+                {
+                    token = Some(found);
+                }
+
+                // A 200 only counts when the body has the declared shape
+                result.schema_errors = match (&call.route.response, &response_json) {
+                    (BodySchema::None | BodySchema::NotProvided, _) => vec![],
+                    (schema, Some(response_json)) => schema.validate(response_json),
+                    (_, None) => vec![String::from("$: response is not JSON")],
+                };
+                result.passed = result.schema_errors.is_empty();
+                if !result.passed {
+                    result.reason = Some(format!(
+                        "response does not match schema: {}",
+                        result.schema_errors.join("; ")
+                    ));
+                }
+
+                if let (true, TestFlow::Crud(resource)) = (call.captures_id, &call.flow) {
+                    let response_json = response_json.unwrap_or(Value::Null);
+                    if let Some(id) = find_id(&response_json, body.as_ref()) {
+                        captured_ids.insert(resource.clone(), id);
+                    }
+                }
+            }
+            Err(e) => result.reason = Some(format!("request failed: {}", e)),
+        }
+
+        results.push(result);
+    }
+
+    results
+}
+
+// Bug report for fix_bug: every schema mismatch with the request and response that caused it
+pub fn schema_mismatch_report(results: &[EndpointResult]) -> Option<String> {
+    let reports: Vec<String> = results
+        .iter()
+        .filter(|result| !result.schema_errors.is_empty())
+        .map(|result| {
+            let request_body = result
+                .request_body
+                .as_ref()
+                .map(Value::to_string)
+                .unwrap_or_else(|| String::from("None"));
+            let response_body: String = result
+                .response_body
+                .as_deref()
+                .unwrap_or_default()
+                .chars()
+                .take(MAX_REPORTED_BODY_CHARS)
+                .collect();
+
+            format!(
+                "ENDPOINT: {} {}\nREQUEST: {} {}\nREQUEST BODY: {}\nRESPONSE STATUS: {}\nRESPONSE BODY: {}\nSCHEMA MISMATCHES:\n- {}",
+                result.method.as_str().to_uppercase(),
+                result.route,
+                result.method.as_str().to_uppercase(),
+                result.url,
+                request_body,
+                result.status.unwrap_or_default(),
+                response_body,
+                result.schema_errors.join("\n- ")
+            )
+        })
+        .collect();
+
+    if reports.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "The running server returned responses that do not match the declared response schema.\n\n{}",
+            reports.join("\n\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    fn route(method: HttpMethod, path: &str, request_body: Value) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: path.contains('{'),
+            method,
+            request_body: BodySchema::from_value(&request_body),
+            response: BodySchema::NotProvided,
+            route: String::from(path),
+            handler: None,
+            json_body_type: None,
+            path_params_type: path.contains('{').then(|| String::from("u64")),
+            requirement_ids: vec![],
+        }
+    }
+
+    fn task_routes() -> Vec<RouteObject> {
+        let task = json!({"id": "number", "name": "string", "completed": "bool"});
+        let user = json!({"id": "number", "username": "string", "password": "string"});
+        vec![
+            route(HttpMethod::Post, "/task", task.clone()),
+            route(HttpMethod::Get, "/task", json!("None")),
+            route(HttpMethod::Get, "/task/{id}", json!("None")),
+            route(HttpMethod::Put, "/task", task),
+            route(HttpMethod::Delete, "/task/{id}", json!("None")),
+            route(HttpMethod::Post, "/register", user.clone()),
+            route(
+                HttpMethod::Post,
+                "/login",
+                json!({"username": "string", "password": "string"}),
+            ),
+            route(HttpMethod::Post, "/logout", json!("None")),
+            route(HttpMethod::Get, "/health", json!("None")),
+        ]
+    }
+
+    #[test]
+    fn test_plan_endpoint_tests() {
+        let calls = plan_endpoint_tests(&task_routes(), true);
+        let plan: Vec<(String, String, TestFlow)> = calls
+            .iter()
+            .map(|call| {
+                (
+                    call.route.method.to_string(),
+                    call.route.route.clone(),
+                    call.flow.clone(),
+                )
+            })
+            .collect();
+        let crud = TestFlow::Crud(String::from("/task"));
+
+        assert_eq!(
+            plan,
+            vec![
+                (
+                    String::from("post"),
+                    String::from("/register"),
+                    TestFlow::Auth
+                ),
+                (String::from("post"), String::from("/login"), TestFlow::Auth),
+                (String::from("post"), String::from("/task"), crud.clone()),
+                (String::from("get"), String::from("/task"), crud.clone()),
+                (
+                    String::from("get"),
+                    String::from("/task/{id}"),
+                    crud.clone()
+                ),
+                (String::from("put"), String::from("/task"), crud.clone()),
+                (String::from("delete"), String::from("/task/{id}"), crud),
+                (
+                    String::from("get"),
+                    String::from("/health"),
+                    TestFlow::Single
+                ),
+                (
+                    String::from("post"),
+                    String::from("/logout"),
+                    TestFlow::Auth
+                ),
+            ]
+        );
+        assert!(calls[2].captures_id);
+        assert_eq!(
+            calls[0].body.as_ref().unwrap()["password"],
+            calls[1].body.as_ref().unwrap()["password"]
+        );
+
+        // Without login in scope, auth routes are tested like any other route
+        let calls = plan_endpoint_tests(&task_routes(), false);
+        assert!(calls.iter().all(|call| call.flow != TestFlow::Auth));
+        assert_eq!(calls.len(), 9);
+    }
+
+    #[test]
+    fn test_synthesize_value() {
+        let schema = BodySchema::from_value(&json!({
+            "id": "number",
+            "email": "string",
+            "tags": ["string"],
+            "done?": "bool"
+        }));
+
+        assert_eq!(
+            synthesize_value(&schema, ""),
+            json!({"id": 1, "email": "plotto@example.com", "tags": ["plotto_tags"], "done": true})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_endpoint_tests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        // Created tasks get id 42, GET /task/42 works and everything else is missing
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buffer = vec![0; 4096];
+                let read = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+
+                let (status, body) = if request.starts_with("POST /task ") {
+                    ("200 OK", r#"{"id":42}"#)
+                } else if request.starts_with("GET /task/42 ") {
+                    (
+                        "200 OK",
+                        r#"{"id":42,"name":"plotto_name","completed":true}"#,
+                    )
+                } else if request.starts_with("GET /task ") {
+                    ("200 OK", r#"[{"id":"42","title":"plotto_name"}]"#)
+                } else {
+                    ("404 Not Found", "")
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let mut list_tasks = route(HttpMethod::Get, "/task", json!("None"));
+        list_tasks.response = BodySchema::from_value(&json!([{"id": "number", "name": "string"}]));
+        let routes = vec![
+            route(
+                HttpMethod::Post,
+                "/task",
+                json!({"id": "number", "name": "string"}),
+            ),
+            list_tasks,
+            route(HttpMethod::Get, "/task/{id}", json!("None")),
+            route(HttpMethod::Get, "/missing", json!("None")),
+        ];
+        let results = run_endpoint_tests(
+            &Client::new(),
+            &base_url,
+            &plan_endpoint_tests(&routes, false),
+        )
+        .await;
+
+        assert!(results[0].passed);
+        assert_eq!(results[2].url, format!("{}/task/42", base_url));
+        assert!(results[2].passed);
+        assert!(!results[3].passed);
+        assert_eq!(results[3].reason.as_deref(), Some("returned status 404"));
+
+        // The list call answers 200 but with the wrong shape
+        assert!(!results[1].passed);
+        assert_eq!(
+            results[1].schema_errors,
+            vec![
+                "$[0].id: expected number, got string",
+                "$[0].name: missing field",
+                "$[0].title: unexpected field",
+            ]
+        );
+        let report = schema_mismatch_report(&results).unwrap();
+        assert!(report.contains("ENDPOINT: GET /task\nREQUEST: GET http://"));
+        assert!(report.contains(r#"RESPONSE BODY: [{"id":"42","title":"plotto_name"}]"#));
+    }
+
+    #[tokio::test]
+    async fn test_run_endpoint_tests_with_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        // Login hands out a token, every other route rejects requests without it
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buffer = vec![0; 4096];
+                let read = stream.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..read]).to_lowercase();
+                let is_authorized = request.contains("authorization: bearer plotto-token");
+
+                let (status, body) = if request.starts_with("post /register ") {
+                    ("200 OK", "{}")
+                } else if request.starts_with("post /login ") {
+                    ("200 OK", r#"{"data":{"access_token":"plotto-token"}}"#)
+                } else if !is_authorized {
+                    ("401 Unauthorized", "")
+                } else if request.starts_with("post /task ") {
+                    ("200 OK", r#"{"id":7}"#)
+                } else {
+                    ("200 OK", "{}")
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let results = run_endpoint_tests(
+            &Client::new(),
+            &base_url,
+            &plan_endpoint_tests(&task_routes(), true),
+        )
+        .await;
+
+        assert_eq!(results.len(), 9);
+        for result in &results {
+            assert!(
+                result.passed,
+                "{} {}: {:?}",
+                result.method, result.route, result.reason
+            );
+        }
+        assert_eq!(results[8].route, "/logout");
+    }
+}
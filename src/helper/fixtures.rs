@@ -0,0 +1,214 @@
+// import
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::helper::general::{fetch_url, FIXTURES_PATH};
+
+// const
+const MAX_SLUG_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchMode {
+    Live,
+    Fixture,
+    Record,
+}
+
+impl FetchMode {
+    // PLOTTO_FETCH_MODE=fixture answers from recorded files, record saves live answers to them
+    pub fn from_env() -> Self {
+        match env::var("PLOTTO_FETCH_MODE") {
+            Ok(mode) if mode.eq_ignore_ascii_case("fixture") => FetchMode::Fixture,
+            Ok(mode) if mode.eq_ignore_ascii_case("record") => FetchMode::Record,
+            _ => FetchMode::Live,
+        }
+    }
+}
+
+// One recorded answer of an external url
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FetchedResponse {
+    #[serde(default)]
+    pub url: String,
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+    // Where the redirects ended, when they did not end at url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+    Request(String),
+    MissingFixture(String, PathBuf),
+    Fixture(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Request(e) => write!(f, "request failed: {}", e),
+            FetchError::MissingFixture(url, path) => {
+                write!(f, "no fixture for {} at {}", url, path.display())
+            }
+            FetchError::Fixture(e) => write!(f, "unreadable fixture: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+// Fetch external urls live, from the fixture directory, or live while recording fixtures
+#[derive(Debug, Clone)]
+pub struct UrlFetcher {
+    pub mode: FetchMode,
+    pub fixtures_dir: PathBuf,
+    client: Client,
+}
+
+impl UrlFetcher {
+    pub fn new(client: Client, mode: FetchMode, fixtures_dir: &Path) -> Self {
+        Self {
+            mode,
+            fixtures_dir: fixtures_dir.to_path_buf(),
+            client,
+        }
+    }
+
+    // PLOTTO_FIXTURES_DIR overrides the default fixture directory
+    pub fn from_env(client: Client) -> Self {
+        let fixtures_dir =
+            env::var("PLOTTO_FIXTURES_DIR").unwrap_or_else(|_| String::from(FIXTURES_PATH));
+        Self::new(client, FetchMode::from_env(), Path::new(&fixtures_dir))
+    }
+
+    pub fn fixture_path(&self, url: &str) -> PathBuf {
+        self.fixtures_dir.join(fixture_file_name(url))
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<FetchedResponse, FetchError> {
+        match self.mode {
+            FetchMode::Live => fetch_url(&self.client, url)
+                .await
+                .map_err(|e| FetchError::Request(e.to_string())),
+            FetchMode::Fixture => self.read_fixture(url),
+            FetchMode::Record => {
+                let response = fetch_url(&self.client, url)
+                    .await
+                    .map_err(|e| FetchError::Request(e.to_string()))?;
+                self.write_fixture(&response)?;
+                Ok(response)
+            }
+        }
+    }
+
+    fn read_fixture(&self, url: &str) -> Result<FetchedResponse, FetchError> {
+        let path = self.fixture_path(url);
+        let json = fs::read_to_string(&path)
+            .map_err(|_| FetchError::MissingFixture(url.to_string(), path.clone()))?;
+        let mut response: FetchedResponse =
+            serde_json::from_str(&json).map_err(|e| FetchError::Fixture(e.to_string()))?;
+        response.url = url.to_string();
+        Ok(response)
+    }
+
+    fn write_fixture(&self, response: &FetchedResponse) -> Result<(), FetchError> {
+        fs::create_dir_all(&self.fixtures_dir).map_err(|e| FetchError::Fixture(e.to_string()))?;
+        let json = serde_json::to_string_pretty(response).expect("Failed to encode fixture");
+        fs::write(self.fixture_path(&response.url), json)
+            .map_err(|e| FetchError::Fixture(e.to_string()))
+    }
+}
+
+// Readable and stable: "<host and path>-<16 hex digit hash>.json"
+pub fn fixture_file_name(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let slug: String = without_scheme
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_SLUG_CHARS)
+        .collect();
+
+    // FNV-1a, unlike DefaultHasher it never changes between Rust versions
+    let hash = url.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    format!("{}-{:016x}.json", slug.trim_matches('_'), hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn test_fixture_file_name() {
+        let name = fixture_file_name("https://api.binance.com/api/v3/klines?symbol=BTCUSDT");
+
+        assert!(name.starts_with("api.binance.com_api_v3_klines_symbol_BTCUSDT-"));
+        assert!(name.ends_with(".json"));
+        assert_ne!(
+            name,
+            fixture_file_name("https://api.binance.com/api/v3/klines?symbol=ETHUSDT")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_fixture() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = [0u8; 1024];
+            let _ = socket.read(&mut buffer).await;
+            let body = r#"{"price":1.5}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+
+        let fixtures_dir = env::temp_dir().join(format!("plotto_fixtures_{}", std::process::id()));
+        let url = format!("http://{}/price?coin=btc", address);
+
+        let recorder = UrlFetcher::new(Client::new(), FetchMode::Record, &fixtures_dir);
+        let recorded = recorder.fetch(&url).await.unwrap();
+        assert_eq!(recorded.status, 200);
+        assert!(recorder.fixture_path(&url).exists());
+
+        // The stub only answers once, so this can only come from the fixture
+        let replayer = UrlFetcher::new(Client::new(), FetchMode::Fixture, &fixtures_dir);
+        let replayed = replayer.fetch(&url).await.unwrap();
+        assert_eq!(replayed, recorded);
+        assert_eq!(
+            replayed.headers.get("content-type").map(String::as_str),
+            Some("application/json")
+        );
+        assert!(matches!(
+            replayer.fetch("http://127.0.0.1:1/missing").await,
+            Err(FetchError::MissingFixture(..))
+        ));
+
+        fs::remove_dir_all(&fixtures_dir).unwrap();
+    }
+}
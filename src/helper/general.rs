@@ -1,19 +1,32 @@
 // import
-use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, Role};
+use openai_dive::v1::resources::chat::{
+    ChatCompletionResponseFormat, ChatCompletionResponseFormatType, ChatMessage,
+    ChatMessageContent, Role,
+};
 use reqwest::Client;
-use std::fs;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 // mod
 use super::command_line::AgentCommand;
-use crate::apis::call_request::call_gpt;
+use super::fixtures::FetchedResponse;
+use super::structured::{is_object_schema, request_until_valid, schema_of, StructuredError};
+use crate::apis::call_request::{call_gpt, call_gpt_with_format};
 
 // const
 pub const CODE_TEMPLATE_PATH: &str =
     "/Users/phat/Desktop/Codespaces/rustc/web_template/src/code_template.rs";
-pub const EXEC_MAIN_PATH: &str = "/Users/phat/Desktop/Codespaces/rustc/web_template/src/main.rs";
 pub const WEB_SERVER_PROJECT_PATH: &str = "/Users/phat/Desktop/Codespaces/rustc/web_template";
 pub const API_SCHEMA_PATH: &str =
     "/Users/phat/Desktop/Codespaces/temp/plotto/schemas/api_schema.json";
+pub const OPENAPI_PATH: &str = "/Users/phat/Desktop/Codespaces/temp/plotto/schemas/openapi.json";
+pub const RUNS_PATH: &str = "/Users/phat/Desktop/Codespaces/temp/plotto/runs";
+pub const FIXTURES_PATH: &str = "/Users/phat/Desktop/Codespaces/temp/plotto/fixtures";
 
 pub fn extend_message_to_agent(func: fn(&str) -> &'static str, input: &str) -> ChatMessage {
     let task = func(input);
@@ -53,9 +66,72 @@ pub async fn ai_task_request(
     gpt_response
 }
 
-pub async fn check_status_code(client: &Client, url: &str) -> Result<u16, reqwest::Error> {
+// Typed answer: the JSON Schema of T goes with the task, and invalid output is sent back with its problems
+pub async fn ai_structured_request<T: DeserializeOwned + JsonSchema>(
+    msg: String,
+    agent_position: &str,
+    agent_task: &str,
+    func: fn(&str) -> &'static str,
+) -> Result<T, StructuredError> {
+    ai_checked_request(msg, agent_position, agent_task, func, |_: &T| vec![]).await
+}
+
+// Like ai_structured_request, with check finding the problems a schema cannot express
+pub async fn ai_checked_request<T, C>(
+    msg: String,
+    agent_position: &str,
+    agent_task: &str,
+    func: fn(&str) -> &'static str,
+    check: C,
+) -> Result<T, StructuredError>
+where
+    T: DeserializeOwned + JsonSchema,
+    C: Fn(&T) -> Vec<String>,
+{
+    AgentCommand::Info.print_agent_message(agent_position, agent_task);
+
+    let schema = schema_of::<T>();
+    let response_format = is_object_schema(&schema).then_some(ChatCompletionResponseFormat {
+        r#type: ChatCompletionResponseFormatType::JsonObject,
+    });
+    let messages = vec![
+        extend_message_to_agent(func, msg.as_str()),
+        ChatMessage {
+            role: Role::System,
+            content: ChatMessageContent::Text(format!(
+                "OUTPUT FORMAT: Print only JSON that is valid against this JSON Schema: {}",
+                schema
+            )),
+            ..Default::default()
+        },
+    ];
+
+    request_until_valid(messages, &schema, agent_position, check, |messages| {
+        call_gpt_with_format(messages, response_format.clone())
+    })
+    .await
+}
+
+// Status code, headers and body text of a GET request
+pub async fn fetch_url(client: &Client, url: &str) -> Result<FetchedResponse, reqwest::Error> {
     let response = client.get(url).send().await?;
-    Ok(response.status().as_u16())
+    let status = response.status().as_u16();
+    let final_url = (reqwest::Url::parse(url).ok().as_ref() != Some(response.url()))
+        .then(|| response.url().to_string());
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = response.text().await?;
+
+    Ok(FetchedResponse {
+        url: url.to_string(),
+        status,
+        headers,
+        body,
+        final_url,
+    })
 }
 
 // Get code template
@@ -64,24 +140,28 @@ pub fn read_code_template() -> String {
     fs::read_to_string(path).expect("Failed to read code template")
 }
 
-// Get Exec Main
-pub fn read_exec_main_code() -> String {
-    let path: String = String::from(EXEC_MAIN_PATH);
-    fs::read_to_string(path).expect("Failed to read exec main.rs file")
-}
-
-// Save new backend code
-pub fn save_backend_code(contents: &String) {
-    let path: String = String::from(EXEC_MAIN_PATH);
-    fs::write(path, contents).expect("Failed to write main.rs.file");
-}
-
 // Save json api endpoint schema
 pub fn save_api_endpoint(api_endpoints: &String) {
     let path: String = String::from(API_SCHEMA_PATH);
     fs::write(path, api_endpoints).expect("Failed to write api endpoints to file");
 }
 
+// Read the json api endpoint schema saved by the last backend run
+pub fn read_api_endpoint() -> Option<String> {
+    fs::read_to_string(API_SCHEMA_PATH).ok()
+}
+
+// Every run keeps its logs and reports in runs/<unix millis>
+pub fn create_run_dir() -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let run_dir = Path::new(RUNS_PATH).join(millis.to_string());
+    fs::create_dir_all(&run_dir).expect("Failed to create run directory");
+    run_dir
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -116,17 +196,16 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_check_status_code() {
+    async fn test_fetch_url() {
         let client = Client::builder()
             .timeout(Duration::from_secs(5))
             .build()
             .unwrap();
 
-        let status_code =
-            check_status_code(&client, "https://api.binance.com/api/v3/exchangeInfo").await;
-        match status_code {
-            Ok(code) => {
-                println!("{}", code);
+        let response = fetch_url(&client, "https://api.binance.com/api/v3/exchangeInfo").await;
+        match response {
+            Ok(response) => {
+                println!("{} {}", response.status, response.body.len());
             }
             Err(error) => {
                 println!("Error checking {}", error);
@@ -0,0 +1,409 @@
+// import
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs,
+    path::{Component, Path},
+};
+
+// const
+pub const ENTRY_FILE_PATH: &str = "src/main.rs";
+const MANIFEST_ROOTS: [&str; 2] = ["src", "tests"];
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ManifestFile {
+    pub path: String,
+    pub contents: String,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ProjectManifest {
+    pub files: Vec<ManifestFile>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ManifestError {
+    Empty,
+    InvalidPath(String),
+    DuplicatePath(String),
+    InvalidDependency(String),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Empty => write!(f, "manifest has no files"),
+            ManifestError::InvalidPath(path) => {
+                write!(
+                    f,
+                    "path {} is not inside src/ or tests/ of the workspace",
+                    path
+                )
+            }
+            ManifestError::DuplicatePath(path) => write!(f, "path {} is listed twice", path),
+            ManifestError::InvalidDependency(name) => {
+                write!(f, "dependency {:?} is not a valid crate requirement", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl ProjectManifest {
+    // Models that ignore the manifest format still print a whole main.rs
+    pub fn from_response(response: &str) -> Self {
+        let trimmed = response.trim();
+        let json = trimmed
+            .strip_prefix("```json")
+            .and_then(|rest| rest.strip_suffix("```"))
+            .unwrap_or(trimmed);
+
+        serde_json::from_str::<ProjectManifest>(json).unwrap_or_else(|_| ProjectManifest {
+            files: vec![ManifestFile {
+                path: String::from(ENTRY_FILE_PATH),
+                contents: response.to_string(),
+            }],
+            dependencies: vec![],
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), ManifestError> {
+        if self.files.is_empty() {
+            return Err(ManifestError::Empty);
+        }
+
+        let mut seen: HashSet<&str> = HashSet::new();
+        for file in &self.files {
+            if !is_workspace_path(&file.path) {
+                return Err(ManifestError::InvalidPath(file.path.clone()));
+            }
+            if !seen.insert(file.path.as_str()) {
+                return Err(ManifestError::DuplicatePath(file.path.clone()));
+            }
+        }
+
+        for dependency in &self.dependencies {
+            let is_valid_name = !dependency.name.is_empty()
+                && dependency
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            let is_valid_version =
+                !dependency.version.trim().is_empty() && !dependency.version.contains(['"', '\n']);
+            let is_valid_features = dependency
+                .features
+                .iter()
+                .all(|feature| !feature.is_empty() && !feature.contains(['"', '\n']));
+
+            if !is_valid_name || !is_valid_version || !is_valid_features {
+                return Err(ManifestError::InvalidDependency(dependency.name.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Only plain relative paths under src/ or tests/, never ".." or absolute paths
+pub fn is_workspace_path(path: &str) -> bool {
+    let components: Vec<Component> = Path::new(path).components().collect();
+
+    components.len() >= 2
+        && components
+            .iter()
+            .all(|component| matches!(component, Component::Normal(_)))
+        && MANIFEST_ROOTS
+            .iter()
+            .any(|root| components[0] == Component::Normal(root.as_ref()))
+}
+
+// Validate then write every file and dependency of the manifest into the workspace
+pub fn write_manifest(project_path: &str, manifest: &ProjectManifest) -> Result<(), ManifestError> {
+    manifest.validate()?;
+
+    for file in &manifest.files {
+        let path = Path::new(project_path).join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Failed to create module directory");
+        }
+        fs::write(&path, &file.contents).expect("Failed to write manifest file");
+    }
+
+    if !manifest.dependencies.is_empty() {
+        let cargo_toml_path = Path::new(project_path).join("Cargo.toml");
+        let cargo_toml = fs::read_to_string(&cargo_toml_path).expect("Failed to read Cargo.toml");
+        fs::write(
+            &cargo_toml_path,
+            add_dependencies(&cargo_toml, &manifest.dependencies),
+        )
+        .expect("Failed to write Cargo.toml");
+    }
+
+    Ok(())
+}
+
+// Append missing dependencies to the [dependencies] table, existing ones win
+pub fn add_dependencies(cargo_toml: &str, dependencies: &[Dependency]) -> String {
+    add_table_dependencies(cargo_toml, "[dependencies]", dependencies)
+}
+
+// Same as add_dependencies for any table, e.g. "[dev-dependencies]"
+pub fn add_table_dependencies(
+    cargo_toml: &str,
+    table: &str,
+    dependencies: &[Dependency],
+) -> String {
+    let mut lines: Vec<String> = cargo_toml.lines().map(String::from).collect();
+
+    let section_start = match lines.iter().position(|line| line.trim() == table) {
+        Some(index) => index,
+        None => {
+            lines.push(String::new());
+            lines.push(String::from(table));
+            lines.len() - 1
+        }
+    };
+    let mut section_end = lines[section_start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map(|offset| section_start + 1 + offset)
+        .unwrap_or(lines.len());
+
+    // Keep a blank line before the next table
+    while section_end > section_start + 1 && lines[section_end - 1].trim().is_empty() {
+        section_end -= 1;
+    }
+
+    let existing: HashSet<String> = lines[section_start + 1..section_end]
+        .iter()
+        .filter_map(|line| line.split('=').next())
+        .map(|name| name.trim().to_string())
+        .collect();
+
+    let mut added: HashSet<&str> = HashSet::new();
+    let new_lines: Vec<String> = dependencies
+        .iter()
+        .filter(|dependency| {
+            !existing.contains(&dependency.name) && added.insert(dependency.name.as_str())
+        })
+        .map(|dependency| {
+            if dependency.features.is_empty() {
+                format!("{} = \"{}\"", dependency.name, dependency.version)
+            } else {
+                let features: Vec<String> = dependency
+                    .features
+                    .iter()
+                    .map(|feature| format!("\"{}\"", feature))
+                    .collect();
+                format!(
+                    "{} = {{ version = \"{}\", features = [{}] }}",
+                    dependency.name,
+                    dependency.version,
+                    features.join(", ")
+                )
+            }
+        })
+        .collect();
+
+    lines.splice(section_end..section_end, new_lines);
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+// Collect every source file of the workspace as relative path -> contents
+pub fn read_project_files(project_path: &str) -> BTreeMap<String, String> {
+    fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(root, &path, files);
+            } else if path.extension().is_some_and(|extension| extension == "rs") {
+                if let (Ok(relative), Ok(contents)) =
+                    (path.strip_prefix(root), fs::read_to_string(&path))
+                {
+                    files.insert(relative.to_string_lossy().replace('\\', "/"), contents);
+                }
+            }
+        }
+    }
+
+    let root = Path::new(project_path);
+    let mut files: BTreeMap<String, String> = BTreeMap::new();
+    for manifest_root in MANIFEST_ROOTS {
+        walk(root, &root.join(manifest_root), &mut files);
+    }
+
+    files
+}
+
+// Render files for a prompt, each one under its own FILE header
+pub fn render_project_files(files: &BTreeMap<String, String>) -> String {
+    files
+        .iter()
+        .map(|(path, contents)| format!("FILE: {}\n{}", path, contents))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_manifest_from_response() {
+        let response = r#"```json
+{
+  "files": [
+    { "path": "src/main.rs", "contents": "mod models;\nfn main() {}\n" },
+    { "path": "src/models.rs", "contents": "pub struct Task;\n" }
+  ],
+  "dependencies": [{ "name": "chrono", "version": "0.4", "features": ["serde"] }]
+}
+```"#;
+
+        let manifest = ProjectManifest::from_response(response);
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.dependencies[0].features, vec!["serde"]);
+        assert_eq!(manifest.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_manifest_falls_back_to_main_file() {
+        let manifest = ProjectManifest::from_response("fn main() {}");
+        assert_eq!(
+            manifest.files,
+            vec![ManifestFile {
+                path: String::from(ENTRY_FILE_PATH),
+                contents: String::from("fn main() {}"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_manifest_refuses_paths_outside_workspace() {
+        for path in [
+            "../main.rs",
+            "/etc/passwd",
+            "src/../../main.rs",
+            "Cargo.toml",
+            "target/debug/app",
+            "src",
+        ] {
+            let manifest = ProjectManifest {
+                files: vec![ManifestFile {
+                    path: String::from(path),
+                    contents: String::new(),
+                }],
+                dependencies: vec![],
+            };
+            assert_eq!(
+                manifest.validate(),
+                Err(ManifestError::InvalidPath(String::from(path)))
+            );
+        }
+
+        assert!(is_workspace_path("src/handlers/task.rs"));
+        assert!(is_workspace_path("tests/api.rs"));
+    }
+
+    #[test]
+    fn test_manifest_refuses_bad_dependencies() {
+        let manifest = ProjectManifest {
+            files: vec![ManifestFile {
+                path: String::from(ENTRY_FILE_PATH),
+                contents: String::new(),
+            }],
+            dependencies: vec![Dependency {
+                name: String::from("evil\"] = 1"),
+                version: String::from("1"),
+                features: vec![],
+            }],
+        };
+
+        assert!(matches!(
+            manifest.validate(),
+            Err(ManifestError::InvalidDependency(_))
+        ));
+    }
+
+    #[test]
+    fn test_add_dependencies() {
+        let cargo_toml = "[package]\nname = \"web_template\"\n\n[dependencies]\nserde = \"1.0\"\n\n[dev-dependencies]\n";
+        let dependencies = vec![
+            Dependency {
+                name: String::from("serde"),
+                version: String::from("2.0"),
+                features: vec![],
+            },
+            Dependency {
+                name: String::from("chrono"),
+                version: String::from("0.4"),
+                features: vec![String::from("serde")],
+            },
+        ];
+
+        assert_eq!(
+            add_dependencies(cargo_toml, &dependencies),
+            "[package]\nname = \"web_template\"\n\n[dependencies]\nserde = \"1.0\"\nchrono = { version = \"0.4\", features = [\"serde\"] }\n\n[dev-dependencies]\n"
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_manifest() {
+        let workspace = env::temp_dir().join(format!("plotto_manifest_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&workspace);
+        fs::create_dir_all(&workspace).unwrap();
+        fs::write(workspace.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        let project_path = workspace.to_str().unwrap();
+
+        let manifest = ProjectManifest {
+            files: vec![
+                ManifestFile {
+                    path: String::from(ENTRY_FILE_PATH),
+                    contents: String::from("mod handlers;\nfn main() {}\n"),
+                },
+                ManifestFile {
+                    path: String::from("src/handlers/mod.rs"),
+                    contents: String::from("pub fn index() {}\n"),
+                },
+            ],
+            dependencies: vec![Dependency {
+                name: String::from("uuid"),
+                version: String::from("1"),
+                features: vec![],
+            }],
+        };
+        write_manifest(project_path, &manifest).unwrap();
+
+        let files = read_project_files(project_path);
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            vec!["src/handlers/mod.rs", "src/main.rs"]
+        );
+        assert!(fs::read_to_string(workspace.join("Cargo.toml"))
+            .unwrap()
+            .contains("[dependencies]\nuuid = \"1\""));
+        assert!(render_project_files(&files).starts_with("FILE: src/handlers/mod.rs\n"));
+
+        let _ = fs::remove_dir_all(&workspace);
+    }
+}
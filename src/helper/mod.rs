@@ -1,2 +1,17 @@
+pub mod api_tests;
+pub mod bench;
 pub mod command_line;
+pub mod compiler;
+pub mod endpoints;
+pub mod fixtures;
 pub mod general;
+pub mod manifest;
+pub mod openapi;
+pub mod patch;
+pub mod routes;
+pub mod schema_check;
+pub mod server;
+pub mod shapes;
+pub mod structured;
+pub mod url_validation;
+pub mod versioning;
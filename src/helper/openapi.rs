@@ -0,0 +1,522 @@
+// import
+use serde_json::{json, Map, Value};
+use std::{collections::HashSet, fmt, fs};
+
+use crate::models::agent::pro::pro_trait::RouteObject;
+use crate::models::agent::pro::route_schema::{BodySchema, PrimitiveType};
+
+// const
+const OPENAPI_VERSION: &str = "3.1.0";
+const API_TITLE: &str = "Plotto generated API";
+const API_VERSION: &str = "0.1.0";
+const OPERATION_KEYS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+const SCHEMA_TYPES: [&str; 7] = [
+    "string", "number", "integer", "boolean", "object", "array", "null",
+];
+const INTEGER_TYPES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct OpenApiError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for OpenApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid OpenAPI document: {}", self.problems.join("; "))
+    }
+}
+
+impl std::error::Error for OpenApiError {}
+
+// Build an OpenAPI 3.1 document from the extracted routes
+pub fn build_openapi_document(description: &str, routes: &[RouteObject]) -> Value {
+    let mut paths = Map::new();
+    let mut operation_ids: HashSet<String> = HashSet::new();
+
+    for route in routes {
+        let (template, param_names) = path_template(&route.route);
+        let param_types = path_param_types(route.path_params_type.as_deref(), param_names.len());
+
+        let parameters: Vec<Value> = param_names
+            .iter()
+            .zip(param_types)
+            .map(|(name, schema)| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": schema
+                })
+            })
+            .collect();
+
+        // Handlers may serve several methods, operationId must stay unique
+        let base_id = route
+            .handler
+            .as_ref()
+            .map(|handler| handler.replace("::", "_"))
+            .unwrap_or_else(|| {
+                format!(
+                    "{}_{}",
+                    route.method,
+                    template
+                        .replace(['/', '{', '}', '-', '.'], "_")
+                        .trim_matches('_')
+                )
+            });
+        let mut operation_id = base_id.clone();
+        let mut suffix = 2;
+        while !operation_ids.insert(operation_id.clone()) {
+            operation_id = format!("{}_{}", base_id, suffix);
+            suffix += 1;
+        }
+
+        let mut operation = Map::new();
+        operation.insert(String::from("operationId"), json!(operation_id));
+        if !parameters.is_empty() {
+            operation.insert(String::from("parameters"), Value::Array(parameters));
+        }
+        if route.request_body != BodySchema::None {
+            operation.insert(
+                String::from("requestBody"),
+                json!({
+                    "required": true,
+                    "content": { "application/json": { "schema": to_json_schema(&route.request_body) } }
+                }),
+            );
+        }
+
+        let mut ok_response = json!({ "description": "Successful response" });
+        if route.response != BodySchema::None {
+            ok_response["content"] =
+                json!({ "application/json": { "schema": to_json_schema(&route.response) } });
+        }
+        operation.insert(String::from("responses"), json!({ "200": ok_response }));
+
+        let path_item = paths
+            .entry(template)
+            .or_insert_with(|| Value::Object(Map::new()));
+        path_item[route.method.as_str()] = Value::Object(operation);
+    }
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": API_TITLE,
+            "version": API_VERSION,
+            "description": description
+        },
+        "paths": paths
+    })
+}
+
+// "/item/{id:\d+}" -> ("/item/{id}", ["id"])
+pub fn path_template(route: &str) -> (String, Vec<String>) {
+    let mut template = String::new();
+    let mut names: Vec<String> = vec![];
+    let mut rest = route;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let name = rest[start + 1..start + end]
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        template.push_str(&rest[..start]);
+        template.push_str(&format!("{{{}}}", name));
+        names.push(name);
+        rest = &rest[start + end + 1..];
+    }
+    template.push_str(rest);
+
+    if !template.starts_with('/') {
+        template.insert(0, '/');
+    }
+
+    (template, names)
+}
+
+// web::Path<u64> or web::Path<(u64, String)> gives the schema of each path segment
+fn path_param_types(path_params_type: Option<&str>, count: usize) -> Vec<Value> {
+    let types: Vec<&str> = match path_params_type {
+        Some(ty) => ty
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split(',')
+            .map(str::trim)
+            .collect(),
+        None => vec![],
+    };
+
+    (0..count)
+        .map(|index| match types.get(index) {
+            Some(ty) if INTEGER_TYPES.contains(ty) && types.len() == count => {
+                json!({ "type": "integer" })
+            }
+            Some(&"f32" | &"f64") if types.len() == count => json!({ "type": "number" }),
+            _ => json!({ "type": "string" }),
+        })
+        .collect()
+}
+
+// Body shapes map onto JSON Schema 2020-12, which OpenAPI 3.1 uses as is
+pub fn to_json_schema(schema: &BodySchema) -> Value {
+    match schema {
+        BodySchema::None => json!({ "type": "null" }),
+        BodySchema::NotProvided => json!({}),
+        BodySchema::Primitive(primitive) => match primitive {
+            PrimitiveType::String => json!({ "type": "string" }),
+            PrimitiveType::Number => json!({ "type": "number" }),
+            PrimitiveType::Bool => json!({ "type": "boolean" }),
+            PrimitiveType::Object => json!({ "type": "object" }),
+            PrimitiveType::Array => json!({ "type": "array" }),
+            PrimitiveType::Any => json!({}),
+        },
+        BodySchema::Array(item) => json!({ "type": "array", "items": to_json_schema(item) }),
+        BodySchema::Object(fields) => {
+            let mut properties = Map::new();
+            let mut required: Vec<&String> = vec![];
+
+            for (name, field) in fields {
+                let property = to_json_schema(&field.schema);
+                if field.is_optional {
+                    properties.insert(name.clone(), nullable(property));
+                } else {
+                    required.push(name);
+                    properties.insert(name.clone(), property);
+                }
+            }
+
+            // Fields outside the shape are a mismatch, as in BodySchema::validate
+            let mut object = json!({
+                "type": "object",
+                "properties": properties,
+                "additionalProperties": false
+            });
+            if !required.is_empty() {
+                object["required"] = json!(required);
+            }
+            object
+        }
+    }
+}
+
+// Optional rust fields come back as null as often as they are left out
+fn nullable(schema: Value) -> Value {
+    match schema.get("type").and_then(Value::as_str) {
+        Some(ty) => {
+            let mut schema = schema.clone();
+            schema["type"] = json!([ty, "null"]);
+            schema
+        }
+        None if schema.as_object().is_some_and(|object| object.is_empty()) => schema,
+        None => json!({ "anyOf": [schema, { "type": "null" }] }),
+    }
+}
+
+// Structural checks of the parts Swagger UI and client generators rely on
+pub fn validate_openapi_document(document: &Value) -> Result<(), OpenApiError> {
+    let mut problems: Vec<String> = vec![];
+
+    let version = document["openapi"].as_str().unwrap_or_default();
+    if !version.starts_with("3.1.") {
+        problems.push(format!("openapi version {:?} is not 3.1.x", version));
+    }
+    for field in ["title", "version"] {
+        if !document["info"][field].is_string() {
+            problems.push(format!("info.{} must be a string", field));
+        }
+    }
+
+    let Some(paths) = document["paths"].as_object() else {
+        problems.push(String::from("paths must be an object"));
+        return Err(OpenApiError { problems });
+    };
+
+    let mut operation_ids: HashSet<&str> = HashSet::new();
+    for (path, path_item) in paths {
+        if !path.starts_with('/') {
+            problems.push(format!("path {} must start with /", path));
+        }
+        let (_, template_params) = path_template(path);
+
+        let Some(operations) = path_item.as_object() else {
+            problems.push(format!("path {} must be an object", path));
+            continue;
+        };
+
+        for (method, operation) in operations {
+            let location = format!("{} {}", method, path);
+            if !OPERATION_KEYS.contains(&method.as_str()) {
+                problems.push(format!("{}: unknown operation", location));
+                continue;
+            }
+
+            if let Some(id) = operation["operationId"].as_str() {
+                if !operation_ids.insert(id) {
+                    problems.push(format!("{}: duplicate operationId {}", location, id));
+                }
+            }
+
+            // Every template parameter declared once as a required path parameter
+            let declared: Vec<&str> = operation["parameters"]
+                .as_array()
+                .map(|parameters| {
+                    parameters
+                        .iter()
+                        .filter(|parameter| parameter["in"] == "path")
+                        .filter_map(|parameter| {
+                            if parameter["required"] != true {
+                                problems.push(format!(
+                                    "{}: path parameter {} must be required",
+                                    location, parameter["name"]
+                                ));
+                            }
+                            parameter["name"].as_str()
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            for name in &template_params {
+                if !declared.contains(&name.as_str()) {
+                    problems.push(format!(
+                        "{}: path parameter {} is not declared",
+                        location, name
+                    ));
+                }
+            }
+            for name in &declared {
+                if !template_params.iter().any(|param| param == name) {
+                    problems.push(format!(
+                        "{}: parameter {} is not in the path",
+                        location, name
+                    ));
+                }
+            }
+
+            match operation["responses"].as_object() {
+                Some(responses) if !responses.is_empty() => {
+                    for (status, response) in responses {
+                        if !response["description"].is_string() {
+                            problems.push(format!(
+                                "{}: response {} needs a description",
+                                location, status
+                            ));
+                        }
+                        for media in response["content"].as_object().into_iter().flatten() {
+                            validate_schema(&media.1["schema"], &location, &mut problems);
+                        }
+                    }
+                }
+                _ => problems.push(format!("{}: responses must not be empty", location)),
+            }
+
+            if let Some(content) = operation["requestBody"]["content"].as_object() {
+                for media in content.values() {
+                    validate_schema(&media["schema"], &location, &mut problems);
+                }
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(OpenApiError { problems })
+    }
+}
+
+fn validate_schema(schema: &Value, location: &str, problems: &mut Vec<String>) {
+    let Some(object) = schema.as_object() else {
+        problems.push(format!("{}: schema must be an object", location));
+        return;
+    };
+
+    let types: Vec<&Value> = match object.get("type") {
+        Some(Value::Array(types)) => types.iter().collect(),
+        Some(ty) => vec![ty],
+        None => vec![],
+    };
+    for ty in types {
+        if !ty.as_str().is_some_and(|ty| SCHEMA_TYPES.contains(&ty)) {
+            problems.push(format!("{}: unknown schema type {}", location, ty));
+        }
+    }
+
+    if let Some(required) = object.get("required").and_then(Value::as_array) {
+        for name in required {
+            let is_defined = name.as_str().is_some_and(|name| {
+                object
+                    .get("properties")
+                    .is_some_and(|p| p.get(name).is_some())
+            });
+            if !is_defined {
+                problems.push(format!(
+                    "{}: required property {} is not defined",
+                    location, name
+                ));
+            }
+        }
+    }
+
+    for property in object
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(|properties| properties.values())
+    {
+        validate_schema(property, location, problems);
+    }
+    if let Some(items) = object.get("items") {
+        validate_schema(items, location, problems);
+    }
+    for variant in object
+        .get("anyOf")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        validate_schema(variant, location, problems);
+    }
+}
+
+// Validate then write the document, an invalid document never reaches the disk
+pub fn save_openapi_document(path: &str, document: &Value) -> Result<(), OpenApiError> {
+    validate_openapi_document(document)?;
+
+    let contents =
+        serde_json::to_string_pretty(document).expect("Failed to encode OpenAPI document");
+    fs::write(path, contents).expect("Failed to write OpenAPI document");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agent::pro::route_schema::HttpMethod;
+
+    fn route(method: HttpMethod, path: &str, request_body: Value, response: Value) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: path.contains('{'),
+            method,
+            request_body: BodySchema::from_value(&request_body),
+            response: BodySchema::from_value(&response),
+            route: String::from(path),
+            handler: None,
+            json_body_type: None,
+            path_params_type: None,
+            requirement_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn test_build_openapi_document() {
+        let mut read_user = route(
+            HttpMethod::Get,
+            "/users/{user_id}/posts/{post_id:\\d+}",
+            json!("None"),
+            json!([{"id": "number", "title": "string"}]),
+        );
+        read_user.path_params_type = Some(String::from("(u64,String)"));
+
+        let routes = vec![
+            route(
+                HttpMethod::Post,
+                "/task",
+                json!({"id": "number", "name": "string", "done?": "bool"}),
+                json!("None"),
+            ),
+            route(
+                HttpMethod::Get,
+                "/task",
+                json!("None"),
+                json!("not_provided"),
+            ),
+            read_user,
+        ];
+
+        let document = build_openapi_document("Manage a task list", &routes);
+        assert_eq!(validate_openapi_document(&document), Ok(()));
+
+        let create = &document["paths"]["/task"]["post"];
+        assert_eq!(create["operationId"], json!("post_task"));
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "done": { "type": ["boolean", "null"] },
+                    "id": { "type": "number" },
+                    "name": { "type": "string" }
+                },
+                "additionalProperties": false,
+                "required": ["id", "name"]
+            })
+        );
+        assert!(create["responses"]["200"].get("content").is_none());
+        assert_eq!(
+            document["paths"]["/task"]["get"]["responses"]["200"]["content"]["application/json"]
+                ["schema"],
+            json!({})
+        );
+
+        let posts = &document["paths"]["/users/{user_id}/posts/{post_id}"]["get"];
+        assert_eq!(
+            posts["parameters"],
+            json!([
+                { "name": "user_id", "in": "path", "required": true, "schema": { "type": "integer" } },
+                { "name": "post_id", "in": "path", "required": true, "schema": { "type": "string" } }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_openapi_document() {
+        let document = json!({
+            "openapi": "3.0.0",
+            "info": { "title": "API" },
+            "paths": {
+                "/item/{id}": {
+                    "get": {
+                        "operationId": "read_item",
+                        "responses": { "200": { "description": "ok" } }
+                    },
+                    "fetch": { "responses": {} },
+                    "put": {
+                        "operationId": "read_item",
+                        "parameters": [{ "name": "id", "in": "path", "required": true }],
+                        "responses": {
+                            "200": {
+                                "description": "ok",
+                                "content": { "application/json": { "schema": { "type": "int" } } }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let problems = validate_openapi_document(&document).unwrap_err().problems;
+        assert_eq!(
+            problems,
+            vec![
+                "openapi version \"3.0.0\" is not 3.1.x",
+                "info.version must be a string",
+                "fetch /item/{id}: unknown operation",
+                "get /item/{id}: path parameter id is not declared",
+                "put /item/{id}: duplicate operationId read_item",
+                "put /item/{id}: unknown schema type \"int\"",
+            ]
+        );
+    }
+}
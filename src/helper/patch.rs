@@ -0,0 +1,436 @@
+// import
+use std::{collections::BTreeMap, fmt};
+
+use super::manifest::{is_workspace_path, ENTRY_FILE_PATH};
+
+// const
+const SEARCH_MARKER: &str = "<<<<<<< SEARCH";
+const DIVIDER_MARKER: &str = "=======";
+const REPLACE_MARKER: &str = ">>>>>>> REPLACE";
+
+#[derive(Debug, PartialEq)]
+pub enum PatchError {
+    NoEdits,
+    MalformedBlock(usize),
+    SearchNotFound(usize),
+    SearchAmbiguous(usize),
+    MalformedHunk(usize),
+    HunkMismatch(usize),
+    UnknownFile(String),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::NoEdits => write!(f, "no search/replace blocks or diff hunks found"),
+            PatchError::MalformedBlock(index) => write!(f, "block {} is malformed", index + 1),
+            PatchError::SearchNotFound(index) => {
+                write!(f, "block {} does not match the current file", index + 1)
+            }
+            PatchError::SearchAmbiguous(index) => {
+                write!(
+                    f,
+                    "block {} matches the current file more than once",
+                    index + 1
+                )
+            }
+            PatchError::MalformedHunk(index) => write!(f, "hunk {} is malformed", index + 1),
+            PatchError::HunkMismatch(index) => {
+                write!(f, "hunk {} does not match the current file", index + 1)
+            }
+            PatchError::UnknownFile(path) => write!(f, "file {} is not in the project", path),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+#[derive(Debug, PartialEq)]
+pub struct SearchReplace {
+    pub path: Option<String>,
+    pub search: String,
+    pub replace: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Hunk {
+    pub path: Option<String>,
+    pub old_start: usize,
+    pub old_lines: Vec<String>,
+    pub new_lines: Vec<String>,
+}
+
+// Apply whichever edit format the model answered with, returns only the files that changed
+pub fn apply_project_edits(
+    files: &BTreeMap<String, String>,
+    response: &str,
+) -> Result<BTreeMap<String, String>, PatchError> {
+    let mut patched: BTreeMap<String, String> = BTreeMap::new();
+
+    if response.contains(SEARCH_MARKER) {
+        for block in parse_search_replace_blocks(response)? {
+            let path = resolve_path(files, &block.path)?;
+            let source = patched.get(&path).or(files.get(&path)).unwrap();
+            let patched_source = apply_search_replace(source, &[block])?;
+            patched.insert(path, patched_source);
+        }
+    } else if response.lines().any(|line| line.starts_with("@@")) {
+        // Hunks of a file are applied together so their line numbers stay consistent
+        let mut hunks_by_path: BTreeMap<String, Vec<Hunk>> = BTreeMap::new();
+        for hunk in parse_unified_diff(response)? {
+            let path = resolve_path(files, &hunk.path)?;
+            hunks_by_path.entry(path).or_default().push(hunk);
+        }
+
+        for (path, hunks) in hunks_by_path {
+            let patched_source = apply_unified_diff(&files[&path], &hunks)?;
+            patched.insert(path, patched_source);
+        }
+    } else {
+        return Err(PatchError::NoEdits);
+    }
+
+    Ok(patched)
+}
+
+// Edits without a file name target the only file, or main.rs
+fn resolve_path(
+    files: &BTreeMap<String, String>,
+    path: &Option<String>,
+) -> Result<String, PatchError> {
+    let path = match path {
+        Some(path) => path.clone(),
+        None if files.len() == 1 => files.keys().next().unwrap().clone(),
+        None => String::from(ENTRY_FILE_PATH),
+    };
+
+    if files.contains_key(&path) {
+        Ok(path)
+    } else {
+        Err(PatchError::UnknownFile(path))
+    }
+}
+
+// "FILE: src/main.rs", "`src/main.rs`" or "### src/main.rs" -> src/main.rs
+fn parse_path_line(line: &str) -> Option<String> {
+    let path = line
+        .trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_start_matches("FILE:")
+        .trim()
+        .trim_matches(|c| c == '`' || c == '*');
+
+    if is_workspace_path(path) {
+        Some(path.to_string())
+    } else {
+        None
+    }
+}
+
+pub fn parse_search_replace_blocks(response: &str) -> Result<Vec<SearchReplace>, PatchError> {
+    let mut blocks: Vec<SearchReplace> = vec![];
+    let mut lines = response.lines();
+    let mut path: Option<String> = None;
+
+    while let Some(line) = lines.next() {
+        if line.trim() != SEARCH_MARKER {
+            // The file name is the last path printed before a block
+            if let Some(path_line) = parse_path_line(line) {
+                path = Some(path_line);
+            }
+            continue;
+        }
+
+        let index = blocks.len();
+        let mut search: Vec<&str> = vec![];
+        let mut replace: Vec<&str> = vec![];
+        let mut is_replace = false;
+        let mut is_closed = false;
+
+        for line in lines.by_ref() {
+            match line.trim() {
+                DIVIDER_MARKER if !is_replace => is_replace = true,
+                REPLACE_MARKER if is_replace => {
+                    is_closed = true;
+                    break;
+                }
+                SEARCH_MARKER => return Err(PatchError::MalformedBlock(index)),
+                _ if is_replace => replace.push(line),
+                _ => search.push(line),
+            }
+        }
+
+        if !is_closed {
+            return Err(PatchError::MalformedBlock(index));
+        }
+
+        blocks.push(SearchReplace {
+            path: path.clone(),
+            search: search.join("\n"),
+            replace: replace.join("\n"),
+        });
+    }
+
+    if blocks.is_empty() {
+        return Err(PatchError::NoEdits);
+    }
+
+    Ok(blocks)
+}
+
+// Every search text must match the current file exactly once
+pub fn apply_search_replace(source: &str, blocks: &[SearchReplace]) -> Result<String, PatchError> {
+    let mut patched = source.to_string();
+
+    for (index, block) in blocks.iter().enumerate() {
+        if block.search.trim().is_empty() {
+            return Err(PatchError::MalformedBlock(index));
+        }
+
+        match patched.matches(block.search.as_str()).count() {
+            0 => return Err(PatchError::SearchNotFound(index)),
+            1 => patched = patched.replacen(block.search.as_str(), block.replace.as_str(), 1),
+            _ => return Err(PatchError::SearchAmbiguous(index)),
+        }
+    }
+
+    Ok(patched)
+}
+
+pub fn parse_unified_diff(diff: &str) -> Result<Vec<Hunk>, PatchError> {
+    let mut hunks: Vec<Hunk> = vec![];
+    let mut current: Option<Hunk> = None;
+    let mut path: Option<String> = None;
+
+    for line in diff.lines() {
+        if let Some(new_path) = line.strip_prefix("+++ ") {
+            if let Some(hunk) = current.take() {
+                hunks.push(hunk);
+            }
+
+            let new_path = new_path.trim();
+            path = Some(new_path.strip_prefix("b/").unwrap_or(new_path).to_string());
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            if let Some(hunk) = current.take() {
+                hunks.push(hunk);
+            }
+
+            let old_start = parse_hunk_start(line).ok_or(PatchError::MalformedHunk(hunks.len()))?;
+            current = Some(Hunk {
+                path: path.clone(),
+                old_start,
+                old_lines: vec![],
+                new_lines: vec![],
+            });
+            continue;
+        }
+
+        let Some(hunk) = current.as_mut() else {
+            continue;
+        };
+
+        // File headers or fences close the current hunk
+        if line.starts_with("--- ") || line.starts_with("diff ") || line.starts_with("```") {
+            hunks.push(current.take().unwrap());
+            continue;
+        }
+
+        if let Some(removed) = line.strip_prefix('-') {
+            hunk.old_lines.push(removed.to_string());
+        } else if let Some(added) = line.strip_prefix('+') {
+            hunk.new_lines.push(added.to_string());
+        } else if line.starts_with('\\') {
+            // "\ No newline at end of file"
+        } else {
+            // Models often drop the leading space on blank context lines
+            let context = line.strip_prefix(' ').unwrap_or(line);
+            hunk.old_lines.push(context.to_string());
+            hunk.new_lines.push(context.to_string());
+        }
+    }
+
+    if let Some(hunk) = current.take() {
+        hunks.push(hunk);
+    }
+
+    if hunks.is_empty() {
+        return Err(PatchError::NoEdits);
+    }
+
+    Ok(hunks)
+}
+
+// "@@ -12,7 +12,9 @@" -> 12
+fn parse_hunk_start(header: &str) -> Option<usize> {
+    let old_range = header
+        .split_whitespace()
+        .find(|part| part.starts_with('-'))?;
+
+    old_range[1..].split(',').next()?.parse::<usize>().ok()
+}
+
+// Locate each hunk by its context nearest to the declared line, then splice it in
+pub fn apply_unified_diff(source: &str, hunks: &[Hunk]) -> Result<String, PatchError> {
+    let mut lines: Vec<String> = source.lines().map(String::from).collect();
+    let mut min_position: usize = 0;
+    let mut offset: isize = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let expected = (hunk.old_start.saturating_sub(1) as isize + offset).max(0) as usize;
+
+        let position = if hunk.old_lines.is_empty() {
+            Some(expected.clamp(min_position, lines.len()))
+        } else {
+            find_hunk_position(&lines, &hunk.old_lines, expected, min_position)
+        }
+        .ok_or(PatchError::HunkMismatch(index))?;
+
+        lines.splice(
+            position..position + hunk.old_lines.len(),
+            hunk.new_lines.iter().cloned(),
+        );
+
+        min_position = position + hunk.new_lines.len();
+        offset += hunk.new_lines.len() as isize - hunk.old_lines.len() as isize;
+    }
+
+    let mut patched = lines.join("\n");
+    if source.ends_with('\n') {
+        patched.push('\n');
+    }
+
+    Ok(patched)
+}
+
+fn find_hunk_position(
+    lines: &[String],
+    old_lines: &[String],
+    expected: usize,
+    min_position: usize,
+) -> Option<usize> {
+    if old_lines.len() > lines.len() {
+        return None;
+    }
+
+    (min_position..=lines.len() - old_lines.len())
+        .filter(|&start| {
+            lines[start..start + old_lines.len()]
+                .iter()
+                .zip(old_lines)
+                .all(|(line, old_line)| line.trim_end() == old_line.trim_end())
+        })
+        .min_by_key(|&start| start.abs_diff(expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "use actix_web::{web, App};\n\nasync fn read_task() {\n    todo!()\n}\n\nfn main() {\n    println!(\"start\");\n}\n";
+
+    fn apply_code_edits(source: &str, response: &str) -> Result<String, PatchError> {
+        let files = BTreeMap::from([(String::from(ENTRY_FILE_PATH), source.to_string())]);
+        let mut patched = apply_project_edits(&files, response)?;
+        Ok(patched.remove(ENTRY_FILE_PATH).unwrap())
+    }
+
+    #[test]
+    fn test_apply_search_replace_blocks() {
+        let response = "Here are the edits:\n```\n<<<<<<< SEARCH\n    todo!()\n=======\n    println!(\"read\");\n>>>>>>> REPLACE\n```";
+
+        let patched = apply_code_edits(SOURCE, response).unwrap();
+        assert!(patched.contains("    println!(\"read\");\n}"));
+        assert!(!patched.contains("todo!()"));
+        assert!(patched.contains("println!(\"start\")"));
+    }
+
+    #[test]
+    fn test_search_replace_must_match_current_file() {
+        let missing = "<<<<<<< SEARCH\nfn missing() {}\n=======\nfn found() {}\n>>>>>>> REPLACE";
+        assert_eq!(
+            apply_code_edits(SOURCE, missing),
+            Err(PatchError::SearchNotFound(0))
+        );
+
+        let ambiguous = "<<<<<<< SEARCH\n}\n=======\n};\n>>>>>>> REPLACE";
+        assert_eq!(
+            apply_code_edits(SOURCE, ambiguous),
+            Err(PatchError::SearchAmbiguous(0))
+        );
+
+        let unclosed = "<<<<<<< SEARCH\n    todo!()\n=======\n    println!(\"read\");";
+        assert_eq!(
+            apply_code_edits(SOURCE, unclosed),
+            Err(PatchError::MalformedBlock(0))
+        );
+    }
+
+    #[test]
+    fn test_apply_unified_diff() {
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -3,3 +3,3 @@\n async fn read_task() {\n-    todo!()\n+    println!(\"read\");\n }\n@@ -8,2 +8,3 @@\n     println!(\"start\");\n+    println!(\"ready\");\n }\n";
+
+        let patched = apply_code_edits(SOURCE, diff).unwrap();
+        assert_eq!(
+            patched,
+            "use actix_web::{web, App};\n\nasync fn read_task() {\n    println!(\"read\");\n}\n\nfn main() {\n    println!(\"start\");\n    println!(\"ready\");\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_tolerates_wrong_line_numbers() {
+        let diff = "@@ -40,2 +40,2 @@\n fn main() {\n-    println!(\"start\");\n+    println!(\"begin\");\n";
+
+        let patched = apply_code_edits(SOURCE, diff).unwrap();
+        assert!(patched.contains("println!(\"begin\")"));
+    }
+
+    #[test]
+    fn test_unified_diff_mismatch() {
+        let diff = "@@ -1,1 +1,1 @@\n-use std::fs;\n+use std::io;\n";
+        assert_eq!(
+            apply_code_edits(SOURCE, diff),
+            Err(PatchError::HunkMismatch(0))
+        );
+    }
+
+    #[test]
+    fn test_apply_project_edits_to_named_files() {
+        let files = BTreeMap::from([
+            (String::from("src/main.rs"), String::from(SOURCE)),
+            (
+                String::from("src/models.rs"),
+                String::from("pub struct Task {\n    pub id: u64,\n}\n"),
+            ),
+        ]);
+
+        let response = "FILE: src/models.rs\n<<<<<<< SEARCH\n    pub id: u64,\n=======\n    pub id: u64,\n    pub name: String,\n>>>>>>> REPLACE\n\n`src/main.rs`\n<<<<<<< SEARCH\n    todo!()\n=======\n    println!(\"read\");\n>>>>>>> REPLACE";
+        let patched = apply_project_edits(&files, response).unwrap();
+        assert_eq!(patched.len(), 2);
+        assert!(patched["src/models.rs"].contains("pub name: String,"));
+        assert!(patched["src/main.rs"].contains("println!(\"read\")"));
+
+        let diff = "--- a/src/models.rs\n+++ b/src/models.rs\n@@ -2,1 +2,2 @@\n     pub id: u64,\n+    pub done: bool,\n";
+        let patched = apply_project_edits(&files, diff).unwrap();
+        assert_eq!(patched.keys().collect::<Vec<_>>(), vec!["src/models.rs"]);
+        assert!(patched["src/models.rs"].contains("pub done: bool,"));
+
+        let unknown =
+            "FILE: src/handlers.rs\n<<<<<<< SEARCH\nfn a() {}\n=======\nfn b() {}\n>>>>>>> REPLACE";
+        assert_eq!(
+            apply_project_edits(&files, unknown),
+            Err(PatchError::UnknownFile(String::from("src/handlers.rs")))
+        );
+    }
+
+    #[test]
+    fn test_no_edits_in_full_rewrite() {
+        assert_eq!(
+            apply_code_edits(SOURCE, "fn main() {}"),
+            Err(PatchError::NoEdits)
+        );
+    }
+}
@@ -0,0 +1,885 @@
+// import
+use quote::ToTokens;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    str::FromStr,
+};
+use syn::{
+    punctuated::Punctuated,
+    visit::{self, Visit},
+    Expr, ExprMethodCall, FnArg, GenericArgument, Item, ItemFn, ItemMod, Lit, Meta, PathArguments,
+    Token, Type, UseTree,
+};
+
+use crate::helper::shapes::{ResponseType, TypeIndex};
+use crate::models::agent::pro::pro_trait::RouteObject;
+use crate::models::agent::pro::route_schema::{BodySchema, HttpMethod};
+
+#[derive(Debug, Clone, Default)]
+pub struct HandlerInfo {
+    pub json_body_type: Option<String>,
+    pub path_params_type: Option<String>,
+    pub attribute_routes: Vec<(String, String)>,
+    pub request_body: Option<Value>,
+    pub response: Option<Value>,
+    pub requirement_ids: Vec<String>,
+}
+
+// Where a chain of builder calls registers its routes
+enum ChainRoot {
+    Plain(String),
+    Scope(String),
+    Resource(String),
+}
+
+// What the use items of one module bring into scope
+#[derive(Debug, Default)]
+struct ModuleUses {
+    // Imported name -> path as written in the use item
+    aliases: HashMap<String, Vec<String>>,
+    globs: Vec<Vec<String>>,
+}
+
+struct RouteCollector<'a> {
+    // Keyed by module path plus fn name, e.g. "handlers::users::list"
+    handlers: &'a HashMap<String, HandlerInfo>,
+    modules: &'a HashMap<Vec<String>, ModuleUses>,
+    module: Vec<String>,
+    routes: Vec<RouteObject>,
+    registered_handlers: HashSet<String>,
+}
+
+// Statically extract actix-web routes from every file of the project
+pub fn extract_routes(files: &BTreeMap<String, String>) -> Vec<RouteObject> {
+    let parsed: Vec<(Vec<String>, syn::File)> = files
+        .iter()
+        .filter_map(|(path, contents)| {
+            let file = syn::parse_file(contents).ok()?;
+            Some((module_path_of_file(path), file))
+        })
+        .collect();
+
+    let files_only: Vec<syn::File> = parsed.iter().map(|(_, file)| file.clone()).collect();
+    let index = TypeIndex::new(&files_only);
+    let mut handlers: HashMap<String, HandlerInfo> = HashMap::new();
+    let mut modules: HashMap<Vec<String>, ModuleUses> = HashMap::new();
+    for (module, file) in &parsed {
+        collect_module(&file.items, module, &index, &mut handlers, &mut modules);
+    }
+
+    let mut collector = RouteCollector {
+        handlers: &handlers,
+        modules: &modules,
+        module: vec![],
+        routes: vec![],
+        registered_handlers: HashSet::new(),
+    };
+    for (module, file) in &parsed {
+        collector.module = module.clone();
+        collector.visit_file(file);
+    }
+
+    // Attribute macro handlers that were never seen in a .service(...) call
+    let mut unregistered: Vec<(&String, &HandlerInfo)> = handlers
+        .iter()
+        .filter(|(name, _)| !collector.registered_handlers.contains(*name))
+        .collect();
+    unregistered.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, info) in unregistered {
+        for (method, path) in &info.attribute_routes {
+            collector.add_route(path, method, name);
+        }
+    }
+
+    collector.routes
+}
+
+// "src/main.rs" -> [], "src/handlers/users.rs" and "src/handlers/users/mod.rs" -> [handlers, users]
+fn module_path_of_file(path: &str) -> Vec<String> {
+    let path = path.strip_prefix("src/").unwrap_or(path);
+    let mut segments: Vec<String> = path
+        .trim_end_matches(".rs")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(String::from)
+        .collect();
+
+    if matches!(
+        segments.last().map(String::as_str),
+        Some("mod" | "main" | "lib")
+    ) {
+        segments.pop();
+    }
+    segments
+}
+
+fn handler_key(module: &[String], name: &str) -> String {
+    module
+        .iter()
+        .map(String::as_str)
+        .chain([name])
+        .collect::<Vec<&str>>()
+        .join("::")
+}
+
+fn collect_module(
+    items: &[Item],
+    module: &[String],
+    index: &TypeIndex,
+    handlers: &mut HashMap<String, HandlerInfo>,
+    modules: &mut HashMap<Vec<String>, ModuleUses>,
+) {
+    let uses = modules.entry(module.to_vec()).or_default();
+    for item in items {
+        if let Item::Use(item_use) = item {
+            collect_uses(&item_use.tree, vec![], uses);
+        }
+    }
+
+    for item in items {
+        match item {
+            Item::Fn(item_fn) => {
+                handlers.insert(
+                    handler_key(module, &item_fn.sig.ident.to_string()),
+                    parse_handler(item_fn, index),
+                );
+            }
+            Item::Mod(item_mod) => {
+                if let Some((_, items)) = &item_mod.content {
+                    let mut inner = module.to_vec();
+                    inner.push(item_mod.ident.to_string());
+                    collect_module(items, &inner, index, handlers, modules);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// use handlers::{users::list as list_users, tasks::*} -> list_users => handlers::users::list, glob handlers::tasks
+fn collect_uses(tree: &UseTree, mut prefix: Vec<String>, uses: &mut ModuleUses) {
+    match tree {
+        UseTree::Path(use_path) => {
+            prefix.push(use_path.ident.to_string());
+            collect_uses(&use_path.tree, prefix, uses);
+        }
+        UseTree::Name(use_name) if use_name.ident == "self" => {
+            if let Some(name) = prefix.last().cloned() {
+                uses.aliases.insert(name, prefix);
+            }
+        }
+        UseTree::Name(use_name) => {
+            let name = use_name.ident.to_string();
+            prefix.push(name.clone());
+            uses.aliases.insert(name, prefix);
+        }
+        UseTree::Rename(use_rename) => {
+            prefix.push(use_rename.ident.to_string());
+            uses.aliases.insert(use_rename.rename.to_string(), prefix);
+        }
+        UseTree::Glob(_) => uses.globs.push(prefix),
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_uses(tree, prefix.clone(), uses);
+            }
+        }
+    }
+}
+
+// crate::, self:: and super:: anchor a path, anything else is relative to the module
+fn anchor_path(module: &[String], segments: &[String]) -> Vec<String> {
+    let mut resolved: Vec<String> = module.to_vec();
+    let mut rest = segments;
+
+    match rest.first().map(String::as_str) {
+        Some("crate") => {
+            resolved.clear();
+            rest = &rest[1..];
+        }
+        Some("self") => rest = &rest[1..],
+        _ => {}
+    }
+    while rest.first().map(String::as_str) == Some("super") {
+        resolved.pop();
+        rest = &rest[1..];
+    }
+
+    resolved.extend(rest.iter().cloned());
+    resolved
+}
+
+fn parse_handler(item_fn: &ItemFn, index: &TypeIndex) -> HandlerInfo {
+    let mut info = HandlerInfo::default();
+
+    // Extractor types: web::Json<T>, web::Form<T> and web::Path<T>
+    for input in &item_fn.sig.inputs {
+        let FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+
+        if let Some((extractor, inner)) = extractor_type(&pat_type.ty) {
+            match extractor.as_str() {
+                "Json" | "Form" => {
+                    info.json_body_type = Some(type_to_string(inner));
+                    info.request_body = resolved_shape(index, inner);
+                }
+                "Path" => info.path_params_type = Some(type_to_string(inner)),
+                _ => {}
+            }
+        }
+    }
+
+    // Unresolved .json(x) values stay "not_provided" for the LLM to fill in
+    info.response = match index.infer_response_type(item_fn) {
+        ResponseType::NoJson => Some(json!("None")),
+        ResponseType::Json(Some(ty)) => resolved_shape(index, &ty),
+        ResponseType::Json(None) => None,
+    };
+
+    info.requirement_ids = doc_requirement_ids(item_fn);
+
+    // #[get("/path")] or #[route("/path", method = "GET", method = "POST")]
+    for attr in &item_fn.attrs {
+        let Some(name) = attr.path().segments.last().map(|s| s.ident.to_string()) else {
+            continue;
+        };
+        let is_method = HttpMethod::from_str(&name).is_ok();
+        if !is_method && name != "route" {
+            continue;
+        }
+
+        let Ok(args) = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated) else {
+            continue;
+        };
+        let Some(path) = args.first().and_then(lit_str) else {
+            continue;
+        };
+
+        if is_method {
+            info.attribute_routes.push((name, path));
+            continue;
+        }
+
+        for arg in args.iter().skip(1) {
+            if let Expr::Assign(assign) = arg {
+                let is_method_arg = assign.left.to_token_stream().to_string() == "method";
+                if let (true, Some(method)) = (is_method_arg, lit_str(&assign.right)) {
+                    info.attribute_routes
+                        .push((method.to_lowercase(), path.clone()));
+                }
+            }
+        }
+    }
+
+    info
+}
+
+// "/// Requirements: R1, R3" above the handler -> ["R1", "R3"]
+fn doc_requirement_ids(item_fn: &ItemFn) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+
+    for attr in &item_fn.attrs {
+        let Meta::NameValue(name_value) = &attr.meta else {
+            continue;
+        };
+        if !name_value.path.is_ident("doc") {
+            continue;
+        }
+        let Some(line) = lit_str(&name_value.value) else {
+            continue;
+        };
+        let Some((label, list)) = line.split_once(':') else {
+            continue;
+        };
+        if !label.trim().eq_ignore_ascii_case("requirements") {
+            continue;
+        }
+
+        for id in list.split(|c: char| c == ',' || c.is_whitespace()) {
+            let id = id.trim().to_uppercase();
+            let is_id =
+                id.len() > 1 && id.starts_with('R') && id[1..].chars().all(|c| c.is_ascii_digit());
+            if is_id && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    ids
+}
+
+// Shapes of types we know nothing about are left unresolved
+fn resolved_shape(index: &TypeIndex, ty: &Type) -> Option<Value> {
+    let shape = index.shape_of_type(ty);
+    (shape != json!("any")).then_some(shape)
+}
+
+// web::Json<Task> -> ("Json", Task)
+fn extractor_type(ty: &Type) -> Option<(String, &Type)> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+
+    arguments.args.iter().find_map(|argument| match argument {
+        GenericArgument::Type(inner) => Some((segment.ident.to_string(), inner)),
+        _ => None,
+    })
+}
+
+pub fn type_to_string(ty: &Type) -> String {
+    ty.to_token_stream().to_string().replace(' ', "")
+}
+
+fn lit_str(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(expr_lit) => match &expr_lit.lit {
+            Lit::Str(lit) => Some(lit.value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// handlers::create_task -> [handlers, create_task]
+fn path_segments(expr: &Expr) -> Option<Vec<String>> {
+    match expr {
+        Expr::Path(expr_path) => Some(
+            expr_path
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+// web::get -> "get", handlers::create_task -> "create_task"
+fn path_last_segment(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(expr_path) => expr_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+// "/api" + "/items/{id}" -> "/api/items/{id}". Only the slash between the two collapses,
+// actix matches a trailing slash exactly so "/items/" stays "/api/items/"
+pub fn join_path(prefix: &str, path: &str) -> String {
+    match (prefix, path) {
+        ("", "") => String::from("/"),
+        (prefix, "") => prefix.to_string(),
+        (prefix, path) => format!(
+            "{}/{}",
+            prefix.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        ),
+    }
+}
+
+// web::get().to(handler) or web::route().method(Method::POST).to(handler)
+fn parse_route_target(expr: &Expr) -> Option<(String, Vec<String>)> {
+    let Expr::MethodCall(to_call) = expr else {
+        return None;
+    };
+    if to_call.method != "to" {
+        return None;
+    }
+
+    let handler = path_segments(to_call.args.first()?)?;
+    let mut method = String::from("get");
+    let mut current: &Expr = &to_call.receiver;
+
+    loop {
+        match current {
+            Expr::MethodCall(inner) => {
+                if inner.method == "method" {
+                    if let Some(name) = inner.args.first().and_then(path_last_segment) {
+                        method = name.to_lowercase();
+                    }
+                }
+                current = &inner.receiver;
+            }
+            Expr::Call(call) => {
+                if let Some(name) = path_last_segment(&call.func) {
+                    if HttpMethod::from_str(&name).is_ok() {
+                        method = name;
+                    }
+                }
+                break;
+            }
+            _ => break,
+        }
+    }
+
+    Some((method, handler))
+}
+
+// Unwind App::new().a().b() into its root expression and calls in order
+fn flatten_chain(call: &ExprMethodCall) -> (&Expr, Vec<&ExprMethodCall>) {
+    let mut calls: Vec<&ExprMethodCall> = vec![call];
+    let mut root: &Expr = &call.receiver;
+
+    while let Expr::MethodCall(inner) = root {
+        calls.push(inner);
+        root = &inner.receiver;
+    }
+
+    calls.reverse();
+    (root, calls)
+}
+
+fn classify_root(root: &Expr, prefix: &str) -> ChainRoot {
+    if let Expr::Call(call) = root {
+        let name = path_last_segment(&call.func);
+        let path = call.args.first().and_then(lit_str);
+
+        match (name.as_deref(), path) {
+            (Some("scope"), Some(path)) => return ChainRoot::Scope(join_path(prefix, &path)),
+            (Some("resource"), Some(path)) => return ChainRoot::Resource(join_path(prefix, &path)),
+            _ => {}
+        }
+    }
+
+    ChainRoot::Plain(prefix.to_string())
+}
+
+impl RouteCollector<'_> {
+    // The handler a path at the call site names, by use items first, then relative to the module,
+    // then from the crate root, and last by a suffix only one handler has
+    fn resolve_handler(&self, segments: &[String]) -> String {
+        let mut candidates: Vec<Vec<String>> = vec![];
+        if let Some(uses) = self.modules.get(&self.module) {
+            if let Some(target) = segments.first().and_then(|name| uses.aliases.get(name)) {
+                let mut imported = target.clone();
+                imported.extend(segments[1..].iter().cloned());
+                candidates.push(anchor_path(&self.module, &imported));
+                candidates.push(anchor_path(&[], &imported));
+            }
+        }
+        candidates.push(anchor_path(&self.module, segments));
+        candidates.push(anchor_path(&[], segments));
+        if let Some(uses) = self.modules.get(&self.module) {
+            for glob in &uses.globs {
+                let mut imported = glob.clone();
+                imported.extend(segments.iter().cloned());
+                candidates.push(anchor_path(&self.module, &imported));
+            }
+        }
+
+        for candidate in candidates {
+            let key = candidate.join("::");
+            if self.handlers.contains_key(&key) {
+                return key;
+            }
+        }
+
+        let suffix = format!("::{}", segments.join("::"));
+        let mut matches = self.handlers.keys().filter(|key| key.ends_with(&suffix));
+        match (matches.next(), matches.next()) {
+            (Some(key), None) => key.clone(),
+            _ => segments.join("::"),
+        }
+    }
+
+    fn add_route(&mut self, route: &str, method: &str, handler: &str) {
+        let Ok(method) = HttpMethod::from_str(method) else {
+            return;
+        };
+        let is_duplicate = self
+            .routes
+            .iter()
+            .any(|existing| existing.route == route && existing.method == method);
+        if is_duplicate {
+            return;
+        }
+
+        let info = self.handlers.get(handler).cloned().unwrap_or_default();
+
+        self.routes.push(RouteObject {
+            is_route_dynamic: route.contains('{'),
+            method,
+            request_body: match (&info.json_body_type, info.request_body) {
+                (Some(_), Some(shape)) => BodySchema::from_value(&shape),
+                (Some(_), None) => BodySchema::NotProvided,
+                (None, _) => BodySchema::None,
+            },
+            response: info
+                .response
+                .map(|shape| BodySchema::from_value(&shape))
+                .unwrap_or(BodySchema::NotProvided),
+            route: route.to_string(),
+            handler: Some(handler.to_string()),
+            json_body_type: info.json_body_type,
+            path_params_type: info.path_params_type,
+            requirement_ids: info.requirement_ids,
+        });
+    }
+
+    fn walk_chain(&mut self, call: &ExprMethodCall, prefix: &str) {
+        let (root, calls) = flatten_chain(call);
+        let chain_root = classify_root(root, prefix);
+
+        for call in calls {
+            let args: Vec<&Expr> = call.args.iter().collect();
+
+            match (
+                call.method.to_string().as_str(),
+                &chain_root,
+                args.as_slice(),
+            ) {
+                ("route", ChainRoot::Resource(path), [target]) => {
+                    if let Some((method, handler)) = parse_route_target(target) {
+                        let handler = self.resolve_handler(&handler);
+                        self.add_route(path, &method, &handler);
+                    }
+                }
+                ("to", ChainRoot::Resource(path), [handler]) => {
+                    if let Some(handler) = path_segments(handler) {
+                        let handler = self.resolve_handler(&handler);
+                        self.add_route(path, "get", &handler);
+                    }
+                }
+                ("route", ChainRoot::Plain(prefix) | ChainRoot::Scope(prefix), [path, target]) => {
+                    if let (Some(path), Some((method, handler))) =
+                        (lit_str(path), parse_route_target(target))
+                    {
+                        let handler = self.resolve_handler(&handler);
+                        self.add_route(&join_path(prefix, &path), &method, &handler);
+                    }
+                }
+                ("service", ChainRoot::Plain(prefix) | ChainRoot::Scope(prefix), [service]) => {
+                    self.walk_service(service, prefix);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn walk_service(&mut self, expr: &Expr, prefix: &str) {
+        match expr {
+            Expr::MethodCall(call) => self.walk_chain(call, prefix),
+            Expr::Path(_) => {
+                let Some(handler) = path_segments(expr) else {
+                    return;
+                };
+                let handler = self.resolve_handler(&handler);
+                let Some(info) = self.handlers.get(&handler) else {
+                    return;
+                };
+
+                for (method, path) in info.attribute_routes.clone() {
+                    self.add_route(&join_path(prefix, &path), &method, &handler);
+                }
+                self.registered_handlers.insert(handler);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for RouteCollector<'_> {
+    fn visit_item_mod(&mut self, item_mod: &'ast ItemMod) {
+        self.module.push(item_mod.ident.to_string());
+        visit::visit_item_mod(self, item_mod);
+        self.module.pop();
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        let (root, calls) = flatten_chain(call);
+        let registers_routes = calls
+            .iter()
+            .any(|call| call.method == "route" || call.method == "service")
+            || !matches!(classify_root(root, ""), ChainRoot::Plain(_));
+
+        // The outermost call of a chain is visited first, so each chain is walked once
+        if registers_routes {
+            self.walk_chain(call, "");
+            return;
+        }
+
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN_RS: &str = r#"
+use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+
+mod handlers;
+
+async fn create_task(app_state: web::Data<AppState>, task: web::Json<Task>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+/// Reads one task
+/// Requirements: R2, r3
+async fn read_task(app_state: web::Data<AppState>, id: web::Path<u64>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+async fn read_all_task(app_state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+#[get("/health")]
+async fn health() -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    HttpServer::new(move || {
+        App::new()
+            .app_data(data.clone())
+            .route("/task", web::post().to(create_task))
+            .route("/task", web::get().to(read_all_task))
+            .route("/task/{id}", web::get().to(read_task))
+            .service(health)
+            .service(
+                web::scope("/api")
+                    .service(handlers::login)
+                    .service(
+                        web::resource("/users/{user_id}")
+                            .route(web::get().to(handlers::read_user))
+                            .route(web::delete().to(handlers::delete_user)),
+                    )
+                    .route("/crypto/", web::route().method(Method::PUT).to(handlers::crypto)),
+            )
+    })
+    .bind("127.0.0.1:8080")?
+    .run()
+    .await
+}
+"#;
+
+    const HANDLERS_RS: &str = r#"
+use actix_web::{post, route, web, HttpResponse, Responder};
+
+/// Requirements: R1
+#[post("/login")]
+pub async fn login(user: web::Json<User>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+#[route("/status", method = "GET", method = "HEAD")]
+pub async fn status() -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+pub async fn read_user(path: web::Path<(u64, String)>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+pub async fn delete_user(path: web::Path<u64>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+pub async fn crypto() -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+"#;
+
+    fn project_files() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            (String::from("src/main.rs"), String::from(MAIN_RS)),
+            (String::from("src/handlers.rs"), String::from(HANDLERS_RS)),
+        ])
+    }
+
+    #[test]
+    fn test_extract_routes() {
+        let routes: Vec<(String, String, String)> = extract_routes(&project_files())
+            .into_iter()
+            .map(|route| {
+                (
+                    route.method.to_string(),
+                    route.route,
+                    route.handler.unwrap(),
+                )
+            })
+            .collect();
+
+        let expected = [
+            ("post", "/task", "create_task"),
+            ("get", "/task", "read_all_task"),
+            ("get", "/task/{id}", "read_task"),
+            ("get", "/health", "health"),
+            ("post", "/api/login", "handlers::login"),
+            ("get", "/api/users/{user_id}", "handlers::read_user"),
+            ("delete", "/api/users/{user_id}", "handlers::delete_user"),
+            ("put", "/api/crypto/", "handlers::crypto"),
+            ("get", "/status", "handlers::status"),
+            ("head", "/status", "handlers::status"),
+        ];
+        let expected: Vec<(String, String, String)> = expected
+            .iter()
+            .map(|(method, route, handler)| {
+                (method.to_string(), route.to_string(), handler.to_string())
+            })
+            .collect();
+
+        assert_eq!(routes, expected);
+    }
+
+    #[test]
+    fn test_extract_route_extractor_types() {
+        let routes = extract_routes(&project_files());
+        let find = |method: &str, route: &str| {
+            routes
+                .iter()
+                .find(|r| r.method.as_str() == method && r.route == route)
+                .unwrap()
+                .clone()
+        };
+
+        let create_task = find("post", "/task");
+        assert_eq!(create_task.json_body_type.as_deref(), Some("Task"));
+        assert_eq!(create_task.request_body, BodySchema::NotProvided);
+        assert!(!create_task.is_route_dynamic);
+
+        let read_task = find("get", "/task/{id}");
+        assert_eq!(read_task.path_params_type.as_deref(), Some("u64"));
+        assert_eq!(read_task.requirement_ids, vec!["R2", "R3"]);
+        assert_eq!(read_task.request_body, BodySchema::None);
+        assert!(read_task.is_route_dynamic);
+
+        let read_user = find("get", "/api/users/{user_id}");
+        assert_eq!(read_user.path_params_type.as_deref(), Some("(u64,String)"));
+
+        let login = find("post", "/api/login");
+        assert_eq!(login.json_body_type.as_deref(), Some("User"));
+        assert_eq!(login.requirement_ids, vec!["R1"]);
+        assert!(find("get", "/health").requirement_ids.is_empty());
+    }
+
+    #[test]
+    fn test_extract_routes_with_same_handler_names() {
+        let main_rs = r#"
+mod api;
+use api::users::list as list_users;
+
+fn main() {
+    App::new()
+        .route("/users", web::get().to(list_users))
+        .route("/tasks", web::get().to(api::tasks::list))
+        .configure(api::tasks::routes);
+}
+"#;
+        let users_rs = r#"
+/// Requirements: R1
+pub async fn list(query: web::Query<Page>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+"#;
+        let tasks_rs = r#"
+/// Requirements: R2
+pub async fn list(owner: web::Path<u64>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+pub fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/tasks/{owner}", web::get().to(list))
+        .route("/mine", web::get().to(super::users::list));
+}
+"#;
+        let files = BTreeMap::from([
+            (String::from("src/main.rs"), String::from(main_rs)),
+            (
+                String::from("src/api/mod.rs"),
+                String::from("pub mod tasks;\npub mod users;"),
+            ),
+            (String::from("src/api/tasks.rs"), String::from(tasks_rs)),
+            (String::from("src/api/users.rs"), String::from(users_rs)),
+        ]);
+
+        let routes: Vec<(String, String, Vec<String>)> = extract_routes(&files)
+            .into_iter()
+            .map(|route| (route.route, route.handler.unwrap(), route.requirement_ids))
+            .collect();
+        let expected = [
+            ("/tasks/{owner}", "api::tasks::list", "R2"),
+            ("/mine", "api::users::list", "R1"),
+            ("/users", "api::users::list", "R1"),
+            ("/tasks", "api::tasks::list", "R2"),
+        ];
+        let expected: Vec<(String, String, Vec<String>)> = expected
+            .iter()
+            .map(|(route, handler, id)| {
+                (route.to_string(), handler.to_string(), vec![id.to_string()])
+            })
+            .collect();
+
+        assert_eq!(routes, expected);
+    }
+
+    #[test]
+    fn test_extract_route_shapes() {
+        let code = r#"
+#[derive(Serialize, Deserialize)]
+struct Task {
+    id: u64,
+    #[serde(rename = "title")]
+    name: String,
+    done: Option<bool>,
+}
+
+async fn create_task(task: web::Json<Task>) -> impl Responder {
+    HttpResponse::Created().json(task.into_inner())
+}
+
+async fn delete_task(id: web::Path<u64>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+async fn stats() -> impl Responder {
+    HttpResponse::Ok().json(compute_stats())
+}
+
+fn main() {
+    App::new()
+        .route("/task", web::post().to(create_task))
+        .route("/task/{id}", web::delete().to(delete_task))
+        .route("/stats", web::get().to(stats));
+}
+"#;
+        let files = BTreeMap::from([(String::from("src/main.rs"), String::from(code))]);
+        let routes = extract_routes(&files);
+        let task_shape = json!({"id": "number", "title": "string", "done?": "bool"});
+
+        assert_eq!(routes[0].request_body.to_value(), task_shape);
+        assert_eq!(routes[0].response.to_value(), task_shape);
+        assert_eq!(routes[1].request_body, BodySchema::None);
+        assert_eq!(routes[1].response, BodySchema::None);
+        assert_eq!(routes[2].response, BodySchema::NotProvided);
+    }
+
+    #[test]
+    fn test_extract_routes_from_unparseable_code() {
+        let files = BTreeMap::from([(
+            String::from("src/main.rs"),
+            String::from("```rust\nfn main() {"),
+        )]);
+        assert!(extract_routes(&files).is_empty());
+    }
+
+    #[test]
+    fn test_join_path() {
+        assert_eq!(join_path("", "/task"), "/task");
+        assert_eq!(join_path("/api/", "/task/"), "/api/task/");
+        assert_eq!(join_path("/api", ""), "/api");
+        assert_eq!(join_path("", ""), "/");
+    }
+}
@@ -0,0 +1,195 @@
+// The one JSON Schema check: structured model answers, BodySchema::validate and the generated
+// tests/api.rs all use it. The generated suite embeds it as `mod schema_check`, so it only
+// depends on serde_json and std
+use serde_json::Value;
+
+// Mismatches of a value against a schema, each one prefixed with its path. Covers the keywords
+// schemars and to_json_schema emit: $ref, type, enum, const, properties, required,
+// additionalProperties, items, anyOf, oneOf, allOf, minimum and maximum
+pub fn check_schema(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    check(value, schema, schema, path, errors);
+}
+
+fn check(value: &Value, schema: &Value, root: &Value, path: &str, errors: &mut Vec<String>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{}: no value is allowed here", path));
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match resolve_ref(root, reference) {
+            Some(target) => check(value, target, root, path, errors),
+            None => errors.push(format!("{}: unknown schema {}", path, reference)),
+        }
+    }
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    if !types.is_empty() && !types.iter().any(|ty| is_schema_type(value, ty)) {
+        let expected = types
+            .iter()
+            .map(|ty| if *ty == "boolean" { "bool" } else { ty })
+            .collect::<Vec<&str>>()
+            .join(" or ");
+        errors.push(format!(
+            "{}: expected {}, got {}",
+            path,
+            expected,
+            value_type(value)
+        ));
+        return;
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{}: {} is not one of {}",
+                path,
+                value,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            errors.push(format!("{}: expected {}, got {}", path, constant, value));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                errors.push(format!("{}: {} is below {}", path, number, minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                errors.push(format!("{}: {} is above {}", path, number, maximum));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        // Declared fields in order, then whatever else the value has
+        for (name, property) in properties.into_iter().flatten() {
+            let field_path = format!("{}.{}", path, name);
+            match object.get(name) {
+                Some(field) => check(field, property, root, &field_path, errors),
+                None if required.contains(&name.as_str()) => {
+                    errors.push(format!("{}: missing field", field_path))
+                }
+                None => {}
+            }
+        }
+        for name in required
+            .iter()
+            .filter(|name| !properties.is_some_and(|properties| properties.contains_key(**name)))
+            .filter(|name| !object.contains_key(**name))
+        {
+            errors.push(format!("{}.{}: missing field", path, name));
+        }
+        for (name, field) in object.iter().filter(|(name, _)| {
+            !properties.is_some_and(|properties| properties.contains_key(*name))
+        }) {
+            let field_path = format!("{}.{}", path, name);
+            match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(format!("{}: unexpected field", field_path))
+                }
+                Some(extra) => check(field, extra, root, &field_path, errors),
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(item), Some(items)) = (schema.get("items"), value.as_array()) {
+        for (index, element) in items.iter().enumerate() {
+            check(element, item, root, &format!("{}[{}]", path, index), errors);
+        }
+    }
+
+    if let Some(Value::Array(all)) = schema.get("allOf") {
+        for sub_schema in all {
+            check(value, sub_schema, root, path, errors);
+        }
+    }
+    for (keyword, is_exactly_one) in [("anyOf", false), ("oneOf", true)] {
+        let Some(Value::Array(options)) = schema.get(keyword) else {
+            continue;
+        };
+        let mut option_errors: Vec<Vec<String>> = options
+            .iter()
+            .map(|option| {
+                let mut found: Vec<String> = vec![];
+                check(value, option, root, path, &mut found);
+                found
+            })
+            .collect();
+        let matches = option_errors
+            .iter()
+            .filter(|found| found.is_empty())
+            .count();
+
+        if matches == 0 {
+            // The closest option explains the mismatch best, the first one on a tie
+            option_errors.sort_by_key(Vec::len);
+            match option_errors.into_iter().next() {
+                Some(closest) if !closest.is_empty() => errors.extend(closest),
+                _ => errors.push(format!("{}: matches none of the {} options", path, keyword)),
+            }
+        } else if is_exactly_one && matches > 1 {
+            errors.push(format!(
+                "{}: matches {} options, expected one",
+                path, matches
+            ));
+        }
+    }
+}
+
+// "#/$defs/Entity" and the older "#/definitions/Entity"
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    root.pointer(pointer)
+}
+
+// 3.0 is an integer, as JSON Schema counts it
+fn is_schema_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
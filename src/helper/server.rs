@@ -0,0 +1,401 @@
+// import
+use reqwest::Client;
+use std::{
+    env, fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    process::{self, Child, Command, Stdio},
+    sync::{Arc, Mutex, Once},
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::{net::TcpStream, signal, time};
+
+// const
+pub const SERVER_PORT_ENV: &str = "PORT";
+const SERVER_HOST: &str = "127.0.0.1";
+const DEFAULT_READY_TIMEOUT_SECS: u64 = 120;
+const READY_POLL_INTERVAL_MS: u64 = 250;
+const LOG_TAIL_LINES: usize = 40;
+const TERMINATE_GRACE_MS: u64 = 3000;
+
+// Process groups of every server still running, for the Ctrl-C handler
+static SERVER_GROUPS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static CTRL_C_HANDLER: Once = Once::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub port: Option<u16>,
+    pub ready_timeout: Duration,
+}
+
+impl ServerConfig {
+    // PLOTTO_SERVER_PORT pins the port, PLOTTO_SERVER_TIMEOUT_SECS bounds compile + startup
+    pub fn from_env() -> Self {
+        let port = env::var("PLOTTO_SERVER_PORT")
+            .ok()
+            .and_then(|port| port.trim().parse::<u16>().ok());
+        let ready_timeout = env::var("PLOTTO_SERVER_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_READY_TIMEOUT_SECS);
+
+        Self {
+            port,
+            ready_timeout: Duration::from_secs(ready_timeout),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LaunchError {
+    NoFreePort(String),
+    Spawn(String),
+    Exited(String),
+    Timeout(Duration),
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchError::NoFreePort(e) => write!(f, "no free port to run the server on: {}", e),
+            LaunchError::Spawn(e) => write!(f, "failed to start cargo run: {}", e),
+            LaunchError::Exited(status) => write!(f, "server exited before it was ready ({})", status),
+            LaunchError::Timeout(timeout) => write!(
+                f,
+                "server did not answer on its port within {}s, does main.rs bind to the {} env var?",
+                timeout.as_secs(),
+                SERVER_PORT_ENV
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LaunchError {}
+
+// Guard over the server process group, dropping it tears the whole group down
+#[derive(Debug)]
+pub struct RunningServer {
+    pub child: Child,
+    pub port: u16,
+    pub log_path: PathBuf,
+    is_stopped: bool,
+}
+
+impl RunningServer {
+    pub fn base_url(&self) -> String {
+        format!("http://{}:{}", SERVER_HOST, self.port)
+    }
+
+    // SIGTERM the group, SIGKILL whatever is left after the grace period
+    pub async fn stop(&mut self) {
+        if self.is_stopped {
+            return;
+        }
+        self.is_stopped = true;
+
+        let pgid = self.child.id();
+        unregister_group(pgid);
+        terminate_group(pgid, &mut self.child).await;
+    }
+}
+
+// A guard dropped without stop() kills the group at once, a grace period would block the runtime
+impl Drop for RunningServer {
+    fn drop(&mut self) {
+        if self.is_stopped {
+            return;
+        }
+        self.is_stopped = true;
+
+        let pgid = self.child.id();
+        unregister_group(pgid);
+        kill_group(pgid, &mut self.child);
+    }
+}
+
+// cargo run and the compiled server share a group that signals can target at once
+fn spawn_in_group(command: &mut Command) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let child = command.spawn()?;
+    register_group(child.id());
+    Ok(child)
+}
+
+fn register_group(pgid: u32) {
+    if let Ok(mut groups) = SERVER_GROUPS.lock() {
+        groups.push(pgid);
+    }
+}
+
+fn unregister_group(pgid: u32) {
+    if let Ok(mut groups) = SERVER_GROUPS.lock() {
+        groups.retain(|group| *group != pgid);
+    }
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: u32, signal: &str) -> bool {
+    Command::new("kill")
+        .arg(format!("-{}", signal))
+        .arg("--")
+        .arg(format!("-{}", pgid))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(unix)]
+async fn terminate_group(pgid: u32, child: &mut Child) {
+    signal_group(pgid, "TERM");
+
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_millis(TERMINATE_GRACE_MS) {
+        // Reap cargo so a zombie does not keep the group looking alive
+        let _ = child.try_wait();
+        if !signal_group(pgid, "0") {
+            return;
+        }
+        time::sleep(Duration::from_millis(100)).await;
+    }
+
+    kill_group(pgid, child);
+}
+
+#[cfg(not(unix))]
+async fn terminate_group(pgid: u32, child: &mut Child) {
+    kill_group(pgid, child);
+}
+
+#[cfg(unix)]
+fn kill_group(pgid: u32, child: &mut Child) {
+    signal_group(pgid, "KILL");
+    let _ = child.wait();
+}
+
+#[cfg(not(unix))]
+fn kill_group(_pgid: u32, child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+// Ctrl-C skips every destructor, so tear the servers down before exiting
+fn install_ctrl_c_handler() {
+    CTRL_C_HANDLER.call_once(|| {
+        tokio::spawn(async {
+            if signal::ctrl_c().await.is_err() {
+                return;
+            }
+
+            let groups: Vec<u32> = SERVER_GROUPS
+                .lock()
+                .map(|groups| groups.clone())
+                .unwrap_or_default();
+            for pgid in groups {
+                #[cfg(unix)]
+                {
+                    signal_group(pgid, "TERM");
+                    time::sleep(Duration::from_millis(500)).await;
+                    signal_group(pgid, "KILL");
+                }
+                #[cfg(not(unix))]
+                let _ = pgid;
+            }
+
+            process::exit(130);
+        });
+    });
+}
+
+// Ask the OS for a port nobody listens on
+pub fn find_free_port() -> std::io::Result<u16> {
+    let listener = TcpListener::bind((SERVER_HOST, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+// Copy every line of a child pipe into the shared run log
+fn capture_output<R: Read + Send + 'static>(pipe: R, label: &'static str, log: Arc<Mutex<File>>) {
+    thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if let Ok(mut log) = log.lock() {
+                let _ = writeln!(log, "[{}] {}", label, line);
+            }
+        }
+    });
+}
+
+// Last lines of the run log, for error reports
+pub fn read_log_tail(log_path: &Path) -> String {
+    let log = fs::read_to_string(log_path).unwrap_or_default();
+    let lines: Vec<&str> = log.lines().collect();
+    lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n")
+}
+
+// cargo run the generated project on its own port and wait until it answers HTTP
+pub async fn launch_server(
+    project_path: &str,
+    config: &ServerConfig,
+    run_dir: &Path,
+) -> Result<RunningServer, LaunchError> {
+    let port = match config.port {
+        Some(port) => port,
+        None => find_free_port().map_err(|e| LaunchError::NoFreePort(e.to_string()))?,
+    };
+
+    fs::create_dir_all(run_dir).map_err(|e| LaunchError::Spawn(e.to_string()))?;
+    let log_path = run_dir.join("server.log");
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .map_err(|e| LaunchError::Spawn(e.to_string()))?;
+    let log = Arc::new(Mutex::new(log));
+
+    install_ctrl_c_handler();
+    let mut child = spawn_in_group(
+        Command::new("cargo")
+            .arg("run")
+            .current_dir(project_path)
+            .env(SERVER_PORT_ENV, port.to_string())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )
+    .map_err(|e| LaunchError::Spawn(e.to_string()))?;
+
+    if let Some(stdout) = child.stdout.take() {
+        capture_output(stdout, "stdout", log.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        capture_output(stderr, "stderr", log);
+    }
+
+    let mut server = RunningServer {
+        child,
+        port,
+        log_path,
+        is_stopped: false,
+    };
+
+    // Stop the half started server before reporting why
+    if let Err(error) = wait_until_ready(&mut server, config.ready_timeout).await {
+        server.stop().await;
+        return Err(error);
+    }
+    Ok(server)
+}
+
+// TCP connect first, then any HTTP answer (even a 404) means the server is up
+async fn wait_until_ready(
+    server: &mut RunningServer,
+    timeout: Duration,
+) -> Result<(), LaunchError> {
+    let address = SocketAddr::from(([127, 0, 0, 1], server.port));
+    let client = Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .expect("Failed to build readiness client");
+    let started = Instant::now();
+
+    while started.elapsed() < timeout {
+        if let Ok(Some(status)) = server.child.try_wait() {
+            return Err(LaunchError::Exited(status.to_string()));
+        }
+
+        let is_listening = TcpStream::connect(address).await.is_ok();
+        if is_listening && client.get(server.base_url()).send().await.is_ok() {
+            return Ok(());
+        }
+
+        time::sleep(Duration::from_millis(READY_POLL_INTERVAL_MS)).await;
+    }
+
+    Err(LaunchError::Timeout(timeout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(command: &mut Command, port: u16) -> RunningServer {
+        RunningServer {
+            child: spawn_in_group(command).unwrap(),
+            port,
+            log_path: PathBuf::new(),
+            is_stopped: false,
+        }
+    }
+
+    #[test]
+    fn test_server_base_url() {
+        let server = guard(&mut Command::new("true"), 8123);
+        assert_eq!(server.base_url(), "http://127.0.0.1:8123");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_drop_tears_down_process_group() {
+        // The shell stands in for cargo, the background sleep for the compiled server
+        let server = guard(
+            Command::new("sh").args(["-c", "sleep 30 & sleep 30"]),
+            find_free_port().unwrap(),
+        );
+        let pgid = server.child.id();
+        thread::sleep(Duration::from_millis(200));
+        assert!(signal_group(pgid, "0"));
+
+        drop(server);
+
+        // Killed orphans stay zombies until init reaps them
+        let started = Instant::now();
+        while signal_group(pgid, "0") && started.elapsed() < Duration::from_secs(2) {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!signal_group(pgid, "0"));
+        assert!(!SERVER_GROUPS.lock().unwrap().contains(&pgid));
+    }
+
+    #[test]
+    fn test_find_free_port() {
+        let port = find_free_port().unwrap();
+        assert!(TcpListener::bind((SERVER_HOST, port)).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stop_terminates_process_group() {
+        let mut server = guard(
+            Command::new("sh").args(["-c", "sleep 30 & sleep 30"]),
+            find_free_port().unwrap(),
+        );
+        let pgid = server.child.id();
+        time::sleep(Duration::from_millis(200)).await;
+
+        // The runtime keeps running other tasks while the group winds down
+        let ticker = tokio::spawn(async {
+            time::sleep(Duration::from_millis(10)).await;
+            Instant::now()
+        });
+        let started = Instant::now();
+        server.stop().await;
+
+        assert!(ticker.await.unwrap() - started < Duration::from_millis(500));
+        assert!(!signal_group(pgid, "0"));
+        assert!(!SERVER_GROUPS.lock().unwrap().contains(&pgid));
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_ready_reports_early_exit() {
+        let mut server = guard(&mut Command::new("false"), find_free_port().unwrap());
+
+        let result = wait_until_ready(&mut server, Duration::from_secs(5)).await;
+        assert!(matches!(result, Err(LaunchError::Exited(_))));
+    }
+}
@@ -0,0 +1,863 @@
+// import
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use syn::{
+    visit::{self, Visit},
+    Attribute, Expr, Fields, FnArg, GenericArgument, ImplItem, Item, ItemEnum, ItemFn, ItemStruct,
+    Lit, LitStr, Pat, PathArguments, ReturnType, Stmt, Type,
+};
+
+// const
+const NUMBER_TYPES: [&str; 14] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize", "f32",
+    "f64",
+];
+const STRING_TYPES: [&str; 8] = [
+    "String",
+    "str",
+    "char",
+    "Uuid",
+    "DateTime",
+    "NaiveDate",
+    "NaiveDateTime",
+    "PathBuf",
+];
+const LIST_TYPES: [&str; 5] = ["Vec", "VecDeque", "HashSet", "BTreeSet", "LinkedList"];
+const MAP_TYPES: [&str; 2] = ["HashMap", "BTreeMap"];
+// Wrappers that serialize exactly like the type they hold
+const TRANSPARENT_TYPES: [&str; 9] = [
+    "Box",
+    "Rc",
+    "Arc",
+    "Cow",
+    "Json",
+    "Data",
+    "Path",
+    "Form",
+    "MutexGuard",
+];
+const MAX_SHAPE_DEPTH: usize = 8;
+
+// Every struct, enum and function signature across the project files
+#[derive(Default)]
+pub struct TypeIndex {
+    structs: HashMap<String, ItemStruct>,
+    enums: HashMap<String, ItemEnum>,
+    functions: HashMap<String, Type>,
+    // (impl type, method name) -> return type, so Task::get and User::get stay apart
+    methods: HashMap<(String, String), Type>,
+}
+
+impl TypeIndex {
+    pub fn new(files: &[syn::File]) -> Self {
+        let mut index = TypeIndex::default();
+        for file in files {
+            index.collect(&file.items);
+        }
+        index
+    }
+
+    fn collect(&mut self, items: &[Item]) {
+        for item in items {
+            match item {
+                Item::Struct(item_struct) => {
+                    self.structs
+                        .insert(item_struct.ident.to_string(), item_struct.clone());
+                }
+                Item::Enum(item_enum) => {
+                    self.enums
+                        .insert(item_enum.ident.to_string(), item_enum.clone());
+                }
+                Item::Fn(item_fn) => {
+                    if let ReturnType::Type(_, ty) = &item_fn.sig.output {
+                        self.functions
+                            .insert(item_fn.sig.ident.to_string(), (**ty).clone());
+                    }
+                }
+                Item::Impl(item_impl) => {
+                    let Some(self_name) = type_name(&item_impl.self_ty) else {
+                        continue;
+                    };
+                    for impl_item in &item_impl.items {
+                        let ImplItem::Fn(impl_fn) = impl_item else {
+                            continue;
+                        };
+                        if let ReturnType::Type(_, ty) = &impl_fn.sig.output {
+                            let ty = replace_self(ty, &item_impl.self_ty);
+                            self.methods
+                                .insert((self_name.clone(), impl_fn.sig.ident.to_string()), ty);
+                        }
+                    }
+                }
+                Item::Mod(item_mod) => {
+                    if let Some((_, items)) = &item_mod.content {
+                        self.collect(items);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // JSON shape of a rust type, e.g. {"id": "number", "tags": ["string"], "note?": "string"}
+    pub fn shape_of_type(&self, ty: &Type) -> Value {
+        self.shape(ty, &mut HashSet::new(), 0)
+    }
+
+    fn shape(&self, ty: &Type, visiting: &mut HashSet<String>, depth: usize) -> Value {
+        if depth > MAX_SHAPE_DEPTH {
+            return json!("any");
+        }
+
+        match ty {
+            Type::Reference(reference) => self.shape(&reference.elem, visiting, depth),
+            Type::Paren(paren) => self.shape(&paren.elem, visiting, depth),
+            Type::Group(group) => self.shape(&group.elem, visiting, depth),
+            Type::Slice(slice) => json!([self.shape(&slice.elem, visiting, depth + 1)]),
+            Type::Array(array) => json!([self.shape(&array.elem, visiting, depth + 1)]),
+            Type::Tuple(tuple) if tuple.elems.is_empty() => json!("None"),
+            Type::Tuple(_) => json!("array"),
+            Type::Path(type_path) => {
+                let Some(segment) = type_path.path.segments.last() else {
+                    return json!("any");
+                };
+                let name = segment.ident.to_string();
+                let inner = first_generic(&segment.arguments);
+
+                if NUMBER_TYPES.contains(&name.as_str()) {
+                    json!("number")
+                } else if STRING_TYPES.contains(&name.as_str()) {
+                    json!("string")
+                } else if name == "bool" {
+                    json!("bool")
+                } else if LIST_TYPES.contains(&name.as_str()) {
+                    match inner {
+                        Some(inner) => json!([self.shape(inner, visiting, depth + 1)]),
+                        None => json!(["any"]),
+                    }
+                } else if MAP_TYPES.contains(&name.as_str()) {
+                    json!("object")
+                } else if TRANSPARENT_TYPES.contains(&name.as_str()) || name == "Option" {
+                    match inner {
+                        Some(inner) => self.shape(inner, visiting, depth),
+                        None => json!("any"),
+                    }
+                } else if let Some(item_struct) = self.structs.get(&name) {
+                    // Recursive structs stop at the first repeat
+                    if !visiting.insert(name.clone()) {
+                        return json!("any");
+                    }
+                    let shape = self.struct_shape(item_struct, visiting, depth);
+                    visiting.remove(&name);
+                    shape
+                } else if let Some(item_enum) = self.enums.get(&name) {
+                    let is_unit_only = item_enum
+                        .variants
+                        .iter()
+                        .all(|variant| matches!(variant.fields, Fields::Unit));
+                    if is_unit_only {
+                        json!("string")
+                    } else {
+                        json!("any")
+                    }
+                } else {
+                    json!("any")
+                }
+            }
+            _ => json!("any"),
+        }
+    }
+
+    fn struct_shape(
+        &self,
+        item_struct: &ItemStruct,
+        visiting: &mut HashSet<String>,
+        depth: usize,
+    ) -> Value {
+        let fields = match &item_struct.fields {
+            Fields::Named(fields) => fields,
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                return self.shape(&fields.unnamed[0].ty, visiting, depth);
+            }
+            Fields::Unnamed(_) => return json!("array"),
+            Fields::Unit => return json!("None"),
+        };
+
+        let rename_all = serde_rename_all(&item_struct.attrs);
+        let mut object = Map::new();
+
+        for field in &fields.named {
+            let attrs = SerdeFieldAttrs::parse(&field.attrs);
+            if attrs.is_skipped {
+                continue;
+            }
+
+            let field_name = field
+                .ident
+                .as_ref()
+                .map(|ident| ident.to_string())
+                .unwrap_or_default();
+            let field_shape = self.shape(&field.ty, visiting, depth + 1);
+
+            // Flattened structs merge their fields into this object
+            if attrs.is_flattened {
+                if let Value::Object(flattened) = field_shape {
+                    object.extend(flattened);
+                }
+                continue;
+            }
+
+            let mut key = attrs
+                .rename
+                .unwrap_or_else(|| apply_rename_all(&field_name, rename_all.as_deref()));
+            if attrs.is_optional || is_option(&field.ty) {
+                key.push('?');
+            }
+
+            object.insert(key, field_shape);
+        }
+
+        Value::Object(object)
+    }
+
+    // Type of the value a handler sends back with .json(x) or by returning web::Json<T>
+    pub fn infer_response_type(&self, item_fn: &ItemFn) -> ResponseType {
+        if let ReturnType::Type(_, ty) = &item_fn.sig.output {
+            if let Some(json_type) = find_wrapped(ty, "Json") {
+                return ResponseType::Json(Some(Box::new(json_type.clone())));
+            }
+        }
+
+        let mut finder = JsonResponseFinder {
+            index: self,
+            bindings: HashMap::new(),
+            responses: vec![],
+        };
+
+        for input in &item_fn.sig.inputs {
+            if let FnArg::Typed(pat_type) = input {
+                if let Pat::Ident(pat_ident) = &*pat_type.pat {
+                    finder
+                        .bindings
+                        .insert(pat_ident.ident.to_string(), (*pat_type.ty).clone());
+                }
+            }
+        }
+        finder.visit_block(&item_fn.block);
+
+        if finder.responses.is_empty() {
+            return ResponseType::NoJson;
+        }
+
+        // Prefer the success response when there are several
+        let response = finder
+            .responses
+            .iter()
+            .find(|(is_success, ty)| *is_success && ty.is_some())
+            .or_else(|| finder.responses.iter().find(|(_, ty)| ty.is_some()))
+            .and_then(|(_, ty)| ty.clone())
+            .map(Box::new);
+
+        ResponseType::Json(response)
+    }
+
+    // Best effort static type of an expression inside a handler
+    fn type_of_expr(&self, expr: &Expr, bindings: &HashMap<String, Type>) -> Option<Type> {
+        match expr {
+            Expr::Path(expr_path) => {
+                let name = expr_path.path.get_ident()?.to_string();
+                bindings.get(&name).cloned()
+            }
+            Expr::Reference(reference) => self.type_of_expr(&reference.expr, bindings),
+            Expr::Paren(paren) => self.type_of_expr(&paren.expr, bindings),
+            Expr::Struct(expr_struct) => {
+                let name = expr_struct.path.segments.last()?.ident.to_string();
+                syn::parse_str::<Type>(&name).ok()
+            }
+            Expr::Field(field) => {
+                let owner = self.type_of_expr(&field.base, bindings)?;
+                let owner_name = type_name(&strip_wrappers(&owner))?;
+                let syn::Member::Named(member) = &field.member else {
+                    return None;
+                };
+                let Fields::Named(fields) = &self.structs.get(&owner_name)?.fields else {
+                    return None;
+                };
+                fields
+                    .named
+                    .iter()
+                    .find(|f| f.ident.as_ref() == Some(member))
+                    .map(|f| f.ty.clone())
+            }
+            Expr::MethodCall(call) => {
+                let method = call.method.to_string();
+                match method.as_str() {
+                    "into_inner" | "clone" | "to_owned" | "to_vec" | "as_ref" | "lock" => {
+                        let receiver = self.type_of_expr(&call.receiver, bindings)?;
+                        Some(strip_wrappers(&receiver))
+                    }
+                    "unwrap" | "expect" | "unwrap_or_default" => {
+                        let receiver = self.type_of_expr(&call.receiver, bindings)?;
+                        find_wrapped(&receiver, "Option")
+                            .or_else(|| find_wrapped(&receiver, "Result"))
+                            .cloned()
+                    }
+                    _ => match self.type_of_expr(&call.receiver, bindings) {
+                        Some(receiver) => {
+                            let owner = type_name(&strip_wrappers(&receiver))?;
+                            self.methods.get(&(owner, method)).cloned()
+                        }
+                        None => self.unique_method(&method),
+                    },
+                }
+            }
+            Expr::Call(call) => {
+                let Expr::Path(func) = &*call.func else {
+                    return None;
+                };
+                let segments: Vec<String> = func
+                    .path
+                    .segments
+                    .iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect();
+
+                // Task::new(...) or a free function with a known return type
+                match segments.as_slice() {
+                    [.., owner, name] if owner.starts_with(char::is_uppercase) => {
+                        self.methods.get(&(owner.clone(), name.clone())).cloned()
+                    }
+                    [.., name] => self.functions.get(name).cloned(),
+                    [] => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // A method of an unknown receiver, only when a single type defines it
+    fn unique_method(&self, method: &str) -> Option<Type> {
+        let mut matches = self
+            .methods
+            .iter()
+            .filter(|((_, name), _)| name == method)
+            .map(|(_, ty)| ty);
+        match (matches.next(), matches.next()) {
+            (Some(ty), None) => Some(ty.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum ResponseType {
+    NoJson,
+    Json(Option<Box<Type>>),
+}
+
+struct JsonResponseFinder<'a> {
+    index: &'a TypeIndex,
+    bindings: HashMap<String, Type>,
+    responses: Vec<(bool, Option<Type>)>,
+}
+
+impl JsonResponseFinder<'_> {
+    fn bind_pattern(&mut self, pat: &Pat, ty: Option<Type>) {
+        match pat {
+            Pat::Ident(pat_ident) => {
+                if let Some(ty) = ty {
+                    self.bindings.insert(pat_ident.ident.to_string(), ty);
+                }
+            }
+            Pat::Type(pat_type) => {
+                self.bind_pattern(&pat_type.pat, Some((*pat_type.ty).clone()));
+            }
+            // Some(task) / Ok(task) unwraps the scrutinee type
+            Pat::TupleStruct(tuple_struct) if tuple_struct.elems.len() == 1 => {
+                let variant = tuple_struct
+                    .path
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident.to_string())
+                    .unwrap_or_default();
+                let inner = ty.and_then(|ty| match variant.as_str() {
+                    "Some" => find_wrapped(&ty, "Option").cloned(),
+                    "Ok" => find_wrapped(&ty, "Result").cloned(),
+                    _ => None,
+                });
+                self.bind_pattern(&tuple_struct.elems[0], inner);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for JsonResponseFinder<'_> {
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        if let Stmt::Local(local) = stmt {
+            let ty = local
+                .init
+                .as_ref()
+                .and_then(|init| self.index.type_of_expr(&init.expr, &self.bindings));
+            self.bind_pattern(&local.pat, ty);
+        }
+        visit::visit_stmt(self, stmt);
+    }
+
+    fn visit_expr_match(&mut self, expr_match: &'ast syn::ExprMatch) {
+        let ty = self.index.type_of_expr(&expr_match.expr, &self.bindings);
+        for arm in &expr_match.arms {
+            self.bind_pattern(&arm.pat, ty.clone());
+        }
+        visit::visit_expr_match(self, expr_match);
+    }
+
+    fn visit_expr_let(&mut self, expr_let: &'ast syn::ExprLet) {
+        let ty = self.index.type_of_expr(&expr_let.expr, &self.bindings);
+        self.bind_pattern(&expr_let.pat, ty);
+        visit::visit_expr_let(self, expr_let);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if call.method == "json" && call.args.len() == 1 {
+            let ty = self.index.type_of_expr(&call.args[0], &self.bindings);
+            self.responses
+                .push((is_success_response(&call.receiver), ty));
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+// HttpResponse::Ok() / Created() / Accepted()
+fn is_success_response(receiver: &Expr) -> bool {
+    let Expr::Call(call) = receiver else {
+        return false;
+    };
+    let Expr::Path(func) = &*call.func else {
+        return false;
+    };
+
+    func.path.segments.last().is_some_and(|segment| {
+        ["Ok", "Created", "Accepted"].contains(&segment.ident.to_string().as_str())
+    })
+}
+
+fn first_generic(arguments: &PathArguments) -> Option<&Type> {
+    let PathArguments::AngleBracketed(arguments) = arguments else {
+        return None;
+    };
+
+    arguments.args.iter().find_map(|argument| match argument {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+// Find T in Wrapper<T>, looking through references and Result<Wrapper<T>, E>
+fn find_wrapped<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    match ty {
+        Type::Reference(reference) => find_wrapped(&reference.elem, wrapper),
+        Type::Path(type_path) => {
+            let segment = type_path.path.segments.last()?;
+            let inner = first_generic(&segment.arguments)?;
+
+            if segment.ident == wrapper {
+                Some(inner)
+            } else if segment.ident == "Result" {
+                find_wrapped(inner, wrapper)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// &web::Json<Task> -> Task
+fn strip_wrappers(ty: &Type) -> Type {
+    match ty {
+        Type::Reference(reference) => strip_wrappers(&reference.elem),
+        Type::Path(type_path) => {
+            let Some(segment) = type_path.path.segments.last() else {
+                return ty.clone();
+            };
+            match first_generic(&segment.arguments) {
+                Some(inner) if TRANSPARENT_TYPES.contains(&segment.ident.to_string().as_str()) => {
+                    strip_wrappers(inner)
+                }
+                _ => ty.clone(),
+            }
+        }
+        _ => ty.clone(),
+    }
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    type_name(ty).as_deref() == Some("Option")
+}
+
+// Self in an impl method return type becomes the impl type
+fn replace_self(ty: &Type, self_ty: &Type) -> Type {
+    let rendered = quote::quote!(#ty).to_string();
+    let self_rendered = quote::quote!(#self_ty).to_string();
+    let replaced = rendered
+        .split(' ')
+        .map(|token| {
+            if token == "Self" {
+                self_rendered.as_str()
+            } else {
+                token
+            }
+        })
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    syn::parse_str::<Type>(&replaced).unwrap_or_else(|_| ty.clone())
+}
+
+#[derive(Default)]
+struct SerdeFieldAttrs {
+    rename: Option<String>,
+    is_skipped: bool,
+    is_flattened: bool,
+    is_optional: bool,
+}
+
+impl SerdeFieldAttrs {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let mut field_attrs = SerdeFieldAttrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if meta.input.peek(syn::Token![=]) {
+                        let lit: LitStr = meta.value()?.parse()?;
+                        field_attrs.rename = Some(lit.value());
+                    } else {
+                        // rename(serialize = "..", deserialize = "..")
+                        meta.parse_nested_meta(|nested| {
+                            let lit: LitStr = nested.value()?.parse()?;
+                            if field_attrs.rename.is_none() || nested.path.is_ident("serialize") {
+                                field_attrs.rename = Some(lit.value());
+                            }
+                            Ok(())
+                        })?;
+                    }
+                    return Ok(());
+                }
+
+                if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                    field_attrs.is_skipped = true;
+                } else if meta.path.is_ident("flatten") {
+                    field_attrs.is_flattened = true;
+                } else if meta.path.is_ident("default") || meta.path.is_ident("skip_serializing_if")
+                {
+                    field_attrs.is_optional = true;
+                }
+
+                // Consume values of attributes we don't care about
+                if meta.input.peek(syn::Token![=]) {
+                    let _: Expr = meta.value()?.parse()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|nested| {
+                        if nested.input.peek(syn::Token![=]) {
+                            let _: Expr = nested.value()?.parse()?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            });
+        }
+
+        field_attrs
+    }
+}
+
+fn serde_rename_all(attrs: &[Attribute]) -> Option<String> {
+    let mut rename_all: Option<String> = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") && meta.input.peek(syn::Token![=]) {
+                let lit: LitStr = meta.value()?.parse()?;
+                rename_all = Some(lit.value());
+            } else if meta.input.peek(syn::Token![=]) {
+                let _: Lit = meta.value()?.parse()?;
+            }
+            Ok(())
+        });
+    }
+
+    rename_all
+}
+
+// Field names are snake_case in rust, convert them like serde's rename_all does
+fn apply_rename_all(field_name: &str, rename_all: Option<&str>) -> String {
+    let words: Vec<&str> = field_name.split('_').filter(|w| !w.is_empty()).collect();
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+            None => String::new(),
+        }
+    };
+
+    match rename_all {
+        Some("lowercase") => field_name.to_lowercase(),
+        Some("UPPERCASE") => field_name.to_uppercase(),
+        Some("PascalCase") => words.iter().map(|w| capitalize(w)).collect(),
+        Some("camelCase") => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_string() } else { capitalize(w) })
+            .collect(),
+        Some("SCREAMING_SNAKE_CASE") => field_name.to_uppercase(),
+        Some("kebab-case") => words.join("-"),
+        Some("SCREAMING-KEBAB-CASE") => words.join("-").to_uppercase(),
+        _ => field_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = r#"
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Task {
+    id: u64,
+    task_name: String,
+    #[serde(rename = "done")]
+    completed: bool,
+    due_date: Option<String>,
+    tags: Vec<String>,
+    owner: User,
+    #[serde(skip)]
+    cache: HashMap<u64, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct User {
+    id: u64,
+    username: String,
+    #[serde(default)]
+    role: Role,
+    #[serde(flatten)]
+    meta: Meta,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Meta {
+    created_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Role {
+    Admin,
+    Member,
+}
+
+struct Database {
+    tasks: HashMap<u64, Task>,
+}
+
+impl Database {
+    fn get(&self, id: &u64) -> Option<&Task> {
+        self.tasks.get(id)
+    }
+
+    fn get_all(&self) -> Vec<&Task> {
+        self.tasks.values().collect()
+    }
+
+    fn load() -> Self {
+        todo!()
+    }
+}
+
+async fn read_task(app_state: web::Data<AppState>, id: web::Path<u64>) -> impl Responder {
+    let db = app_state.db.lock().unwrap();
+    match db.get(&id.into_inner()) {
+        Some(task) => HttpResponse::Ok().json(task),
+        None => HttpResponse::NotFound().json("missing"),
+    }
+}
+
+async fn read_all_task(app_state: web::Data<AppState>) -> impl Responder {
+    let db = app_state.db.lock().unwrap();
+    let tasks = db.get_all();
+    HttpResponse::Ok().json(tasks)
+}
+
+async fn echo_owner(task: web::Json<Task>) -> impl Responder {
+    HttpResponse::Ok().json(task.owner.clone())
+}
+
+async fn create_task(task: web::Json<Task>) -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+async fn typed(user: web::Json<User>) -> Result<web::Json<User>, Error> {
+    Ok(user)
+}
+"#;
+
+    fn index_and_fns() -> (TypeIndex, HashMap<String, ItemFn>) {
+        let file = syn::parse_file(CODE).unwrap();
+        let fns = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Fn(item_fn) => Some((item_fn.sig.ident.to_string(), item_fn.clone())),
+                _ => None,
+            })
+            .collect();
+        (TypeIndex::new(&[file]), fns)
+    }
+
+    fn response_shape(index: &TypeIndex, item_fn: &ItemFn) -> Value {
+        match index.infer_response_type(item_fn) {
+            ResponseType::Json(Some(ty)) => index.shape_of_type(&ty),
+            ResponseType::Json(None) => json!("not_provided"),
+            ResponseType::NoJson => json!("None"),
+        }
+    }
+
+    #[test]
+    fn test_shape_of_struct() {
+        let (index, _) = index_and_fns();
+        let task: Type = syn::parse_str("Task").unwrap();
+
+        assert_eq!(
+            index.shape_of_type(&task),
+            json!({
+                "id": "number",
+                "taskName": "string",
+                "done": "bool",
+                "dueDate?": "string",
+                "tags": ["string"],
+                "owner": {
+                    "id": "number",
+                    "username": "string",
+                    "role?": "string",
+                    "created_at": "number"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_infer_response_shapes() {
+        let (index, fns) = index_and_fns();
+        let user_shape = json!({
+            "id": "number",
+            "username": "string",
+            "role?": "string",
+            "created_at": "number"
+        });
+
+        let read_task = response_shape(&index, &fns["read_task"]);
+        assert_eq!(read_task["done"], json!("bool"));
+
+        let read_all_task = response_shape(&index, &fns["read_all_task"]);
+        assert!(read_all_task.is_array());
+        assert_eq!(read_all_task[0]["taskName"], json!("string"));
+
+        assert_eq!(response_shape(&index, &fns["echo_owner"]), user_shape);
+        assert_eq!(response_shape(&index, &fns["create_task"]), json!("None"));
+        assert_eq!(response_shape(&index, &fns["typed"]), user_shape);
+    }
+
+    #[test]
+    fn test_self_return_type() {
+        let (index, _) = index_and_fns();
+        assert_eq!(
+            index
+                .methods
+                .get(&("Database".to_string(), "load".to_string()))
+                .and_then(type_name)
+                .as_deref(),
+            Some("Database")
+        );
+    }
+
+    #[test]
+    fn test_methods_keyed_by_type() {
+        let file = syn::parse_file(
+            r#"
+struct Task { id: u64 }
+struct User { name: String }
+struct Tasks;
+struct Users;
+
+impl Tasks {
+    fn get(&self, id: u64) -> Option<Task> { None }
+    fn new() -> Self { Tasks }
+}
+
+impl Users {
+    fn get(&self, id: u64) -> Option<User> { None }
+    fn new() -> Self { Users }
+}
+
+async fn read_user() -> impl Responder {
+    let users = Users::new();
+    HttpResponse::Ok().json(users.get(1))
+}
+
+async fn read_task(tasks: web::Data<Tasks>) -> impl Responder {
+    HttpResponse::Ok().json(tasks.get(1))
+}
+
+async fn guessed(store: web::Data<Store>) -> impl Responder {
+    HttpResponse::Ok().json(store.get(1))
+}
+
+async fn unbound() -> impl Responder {
+    HttpResponse::Ok().json(task)
+}
+"#,
+        )
+        .unwrap();
+        let fns: HashMap<String, ItemFn> = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Fn(item_fn) => Some((item_fn.sig.ident.to_string(), item_fn.clone())),
+                _ => None,
+            })
+            .collect();
+        let index = TypeIndex::new(&[file]);
+
+        assert_eq!(
+            response_shape(&index, &fns["read_user"]),
+            json!({"name": "string"})
+        );
+        assert_eq!(
+            response_shape(&index, &fns["read_task"]),
+            json!({"id": "number"})
+        );
+        // Two types define get and Store is unknown, so nothing is guessed
+        assert_eq!(
+            response_shape(&index, &fns["guessed"]),
+            json!("not_provided")
+        );
+        assert_eq!(
+            response_shape(&index, &fns["unbound"]),
+            json!("not_provided")
+        );
+    }
+}
@@ -0,0 +1,378 @@
+// import
+use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, Role};
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{fmt, future::Future};
+
+use super::{command_line::AgentCommand, schema_check::check_schema};
+
+// const
+pub const MAX_STRUCTURED_ATTEMPTS: u8 = 3;
+const MAX_REPORTED_PROBLEMS: usize = 20;
+
+#[derive(Debug)]
+pub struct StructuredError {
+    pub attempts: u8,
+    pub problems: Vec<String>,
+    pub last_response: String,
+}
+
+impl fmt::Display for StructuredError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no valid output after {} attempts: {}",
+            self.attempts,
+            self.problems.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for StructuredError {}
+
+// JSON Schema of T as sent to the model and checked against its answer
+pub fn schema_of<T: JsonSchema>() -> Value {
+    schema_for!(T).as_value().clone()
+}
+
+// JSON mode only guarantees an object, so arrays are asked for in plain text
+pub fn is_object_schema(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("object")
+}
+
+// The first object or array in the text that parses, e.g. out of a ```json fence or a sentence
+pub fn extract_json(text: &str) -> Option<String> {
+    let mut first_candidate: Option<String> = None;
+
+    for (start, c) in text.char_indices() {
+        if c != '{' && c != '[' {
+            continue;
+        }
+        let Some(end) = balanced_end(&text[start..]) else {
+            continue;
+        };
+
+        let candidate = remove_trailing_commas(&text[start..start + end]);
+        if serde_json::from_str::<Value>(&candidate).is_ok() {
+            return Some(candidate);
+        }
+        first_candidate.get_or_insert(candidate);
+    }
+
+    first_candidate
+}
+
+// Byte length of the bracketed value at the start of text, strings and escapes included
+fn balanced_end(text: &str) -> Option<usize> {
+    let mut closers: Vec<char> = vec![];
+    let mut is_in_string = false;
+    let mut is_escaped = false;
+
+    for (index, c) in text.char_indices() {
+        if is_in_string {
+            match c {
+                _ if is_escaped => is_escaped = false,
+                '\\' => is_escaped = true,
+                '"' => is_in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => is_in_string = true,
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '}' | ']' => {
+                if closers.pop() != Some(c) {
+                    return None;
+                }
+                if closers.is_empty() {
+                    return Some(index + c.len_utf8());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// [1, 2,] and {"a": 1,} are common slips, drop the comma
+pub fn remove_trailing_commas(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut repaired = String::with_capacity(json.len());
+    let mut is_in_string = false;
+    let mut is_escaped = false;
+
+    for (index, c) in chars.iter().enumerate() {
+        if is_in_string {
+            match c {
+                _ if is_escaped => is_escaped = false,
+                '\\' => is_escaped = true,
+                '"' => is_in_string = false,
+                _ => {}
+            }
+        } else if *c == '"' {
+            is_in_string = true;
+        } else if *c == ',' {
+            let next = chars[index + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        repaired.push(*c);
+    }
+
+    repaired
+}
+
+// Decode an answer into T, or every problem found on the way for the next attempt
+pub fn parse_structured<T: DeserializeOwned>(
+    response: &str,
+    schema: &Value,
+) -> Result<T, Vec<String>> {
+    let json = extract_json(response)
+        .ok_or_else(|| vec![String::from("$: no JSON object or array in the output")])?;
+    let value: Value =
+        serde_json::from_str(&json).map_err(|e| vec![format!("$: invalid JSON: {}", e)])?;
+
+    let mut problems: Vec<String> = vec![];
+    check_schema(&value, schema, "$", &mut problems);
+    if !problems.is_empty() {
+        problems.truncate(MAX_REPORTED_PROBLEMS);
+        return Err(problems);
+    }
+
+    serde_json::from_value(value).map_err(|e| vec![format!("$: {}", e)])
+}
+
+// Ask until the answer decodes into T and passes check, showing the model its own output and
+// the problems with it after every failed attempt
+pub async fn request_until_valid<T, C, F, Fut>(
+    mut messages: Vec<ChatMessage>,
+    schema: &Value,
+    agent_position: &str,
+    check: C,
+    mut ask: F,
+) -> Result<T, StructuredError>
+where
+    T: DeserializeOwned,
+    C: Fn(&T) -> Vec<String>,
+    F: FnMut(Vec<ChatMessage>) -> Fut,
+    Fut: Future<Output = String>,
+{
+    let mut problems: Vec<String> = vec![];
+    let mut last_response = String::new();
+
+    for attempt in 1..=MAX_STRUCTURED_ATTEMPTS {
+        last_response = ask(messages.clone()).await;
+        problems = match parse_structured::<T>(&last_response, schema) {
+            Ok(value) => {
+                let found = check(&value);
+                if found.is_empty() {
+                    return Ok(value);
+                }
+                found
+            }
+            Err(found) => found,
+        };
+
+        AgentCommand::Issue.print_agent_message(
+            agent_position,
+            format!(
+                "Invalid output ({}/{}): {}",
+                attempt,
+                MAX_STRUCTURED_ATTEMPTS,
+                problems.join("; ")
+            )
+            .as_str(),
+        );
+        messages.push(ChatMessage {
+            role: Role::Assistant,
+            content: ChatMessageContent::Text(last_response.clone()),
+            ..Default::default()
+        });
+        messages.push(ChatMessage {
+            role: Role::User,
+            content: ChatMessageContent::Text(format!(
+                "The output above is invalid:\n- {}\nPrint the corrected JSON only.",
+                problems.join("\n- ")
+            )),
+            ..Default::default()
+        });
+    }
+
+    Err(StructuredError {
+        attempts: MAX_STRUCTURED_ATTEMPTS,
+        problems,
+        last_response,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::helper::openapi::to_json_schema;
+    use crate::models::agent::pro::route_schema::BodySchema;
+
+    #[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    enum Storage {
+        JsonFile,
+        Sqlite,
+    }
+
+    #[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+    struct Plan {
+        name: String,
+        pages: u32,
+        storage: Storage,
+        #[serde(default)]
+        note: Option<String>,
+    }
+
+    #[test]
+    fn test_extract_json() {
+        assert_eq!(
+            extract_json("Sure! Here it is:\n```json\n{\"a\": [1, 2,],}\n```\nHope it helps [1]."),
+            Some(String::from("{\"a\": [1, 2]}"))
+        );
+        assert_eq!(
+            extract_json("See [the docs] first. [\"https://a.io/x?q=[1]\"]"),
+            Some(String::from("[\"https://a.io/x?q=[1]\"]"))
+        );
+        assert_eq!(
+            extract_json(r#"{"text": "a } inside, ] and \" quote",}"#),
+            Some(String::from(r#"{"text": "a } inside, ] and \" quote"}"#))
+        );
+        assert_eq!(extract_json("no json here"), None);
+    }
+
+    #[test]
+    fn test_parse_structured() {
+        let schema = schema_of::<Plan>();
+        assert!(is_object_schema(&schema));
+        assert!(!is_object_schema(&schema_of::<Vec<String>>()));
+
+        let plan: Plan = parse_structured(
+            "The plan: {\"name\": \"books\", \"pages\": 3, \"storage\": \"sqlite\"}",
+            &schema,
+        )
+        .unwrap();
+        assert_eq!(plan.storage, Storage::Sqlite);
+        assert_eq!(plan.note, None);
+
+        let problems = parse_structured::<Plan>(
+            "{\"pages\": -1, \"storage\": \"postgres\", \"note\": 5}",
+            &schema,
+        )
+        .unwrap_err();
+        assert!(problems.contains(&String::from("$.name: missing field")));
+        assert!(problems.contains(&String::from("$.pages: -1 is below 0")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.starts_with("$.storage: ")));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.starts_with("$.note: expected string or null")));
+
+        assert_eq!(
+            parse_structured::<Plan>("nothing to see", &schema).unwrap_err(),
+            vec!["$: no JSON object or array in the output"]
+        );
+    }
+
+    #[test]
+    fn test_check_schema() {
+        let check = |value: Value, schema: &Value| {
+            let mut errors: Vec<String> = vec![];
+            check_schema(&value, schema, "$", &mut errors);
+            errors
+        };
+
+        // The rule BodySchema::validate uses for api responses
+        let response = to_json_schema(&BodySchema::from_value(
+            &json!({"id": "number", "done?": "bool"}),
+        ));
+        assert!(check(json!({"id": 3.0, "done": null}), &response).is_empty());
+        assert_eq!(
+            check(json!({"done": "yes", "extra": 1}), &response),
+            vec![
+                "$.done: expected bool or null, got string",
+                "$.id: missing field",
+                "$.extra: unexpected field",
+            ]
+        );
+
+        // And the one structured answers get, with $ref, enum and oneOf from schemars
+        let schema = json!({
+            "$defs": { "Storage": { "type": "string", "enum": ["json_file", "sqlite"] } },
+            "type": "object",
+            "properties": {
+                "pages": { "type": "integer" },
+                "storage": { "$ref": "#/$defs/Storage" },
+                "id": { "oneOf": [{ "type": "integer" }, { "type": "number" }] }
+            }
+        });
+        assert!(check(json!({"pages": 3.0, "storage": "sqlite"}), &schema).is_empty());
+        assert_eq!(
+            check(
+                json!({"pages": 2.5, "storage": "postgres", "id": 1}),
+                &schema
+            ),
+            vec![
+                "$.id: matches 2 options, expected one",
+                "$.pages: expected integer, got number",
+                r#"$.storage: "postgres" is not one of ["json_file","sqlite"]"#,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_until_valid() {
+        let schema = schema_of::<Plan>();
+        let check = |plan: &Plan| {
+            if plan.pages > 10 {
+                vec![String::from("$.pages: at most 10 pages")]
+            } else {
+                vec![]
+            }
+        };
+
+        let mut answers = vec![
+            "{\"name\": \"books\"}",
+            "{\"name\": \"books\", \"pages\": 30, \"storage\": \"sqlite\"}",
+            "Fixed: {\"name\": \"books\", \"pages\": 3, \"storage\": \"sqlite\",}",
+        ]
+        .into_iter();
+        let mut seen_messages: Vec<usize> = vec![];
+        let plan: Plan = request_until_valid(vec![], &schema, "Tester", check, |messages| {
+            seen_messages.push(messages.len());
+            let answer = answers.next().unwrap().to_string();
+            async move { answer }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(plan.pages, 3);
+        assert_eq!(seen_messages, vec![0, 2, 4]);
+
+        let error = request_until_valid::<Plan, _, _, _>(
+            vec![],
+            &schema,
+            "Tester",
+            |_| vec![],
+            |_| async { String::from("I cannot do that") },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.attempts, MAX_STRUCTURED_ATTEMPTS);
+        assert_eq!(error.last_response, "I cannot do that");
+    }
+}
@@ -0,0 +1,330 @@
+// import
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::helper::fixtures::{FetchedResponse, UrlFetcher};
+
+// const
+const ERROR_FIELDS: [&str; 7] = [
+    "error",
+    "errors",
+    "message",
+    "status_message",
+    "detail",
+    "msg",
+    "fault",
+];
+// Fields that only ever carry an error, unlike message or detail
+const PLAIN_ERROR_FIELDS: [&str; 3] = ["error", "errors", "fault"];
+const KEY_ERROR_KEYWORDS: [&str; 9] = [
+    "api key",
+    "api_key",
+    "apikey",
+    "access key",
+    "access_key",
+    "token",
+    "unauthorized",
+    "authenticat",
+    "subscription",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerdictKind {
+    Usable,
+    AuthRequired,
+    NotJson,
+    BadStatus,
+    Unreachable,
+    Duplicate,
+}
+
+// Why an external url is kept or dropped
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UrlVerdict {
+    pub url: String,
+    pub kind: VerdictKind,
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    pub reason: String,
+}
+
+impl UrlVerdict {
+    pub fn is_usable(&self) -> bool {
+        self.kind == VerdictKind::Usable
+    }
+}
+
+// Scheme and host are case insensitive and a trailing slash changes nothing
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let (host, path) = match rest.find(['/', '?', '#']) {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    let path = match path.split_once('?') {
+        Some((path, query)) => format!("{}?{}", path.trim_end_matches('/'), query),
+        None => path.trim_end_matches('/').to_string(),
+    };
+
+    format!(
+        "{}://{}{}",
+        scheme.to_lowercase(),
+        host.to_lowercase(),
+        path
+    )
+}
+
+// First url of every normalized form, plus a Duplicate verdict for the others
+pub fn dedupe_urls(urls: &[String]) -> (Vec<String>, Vec<UrlVerdict>) {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut unique: Vec<String> = vec![];
+    let mut duplicates: Vec<UrlVerdict> = vec![];
+
+    for url in urls {
+        let url = url.trim().to_string();
+        match seen.get(&normalize_url(&url)) {
+            Some(first) => duplicates.push(UrlVerdict {
+                reason: format!("duplicate of {}", first),
+                url,
+                kind: VerdictKind::Duplicate,
+                status: None,
+                final_url: None,
+            }),
+            None => {
+                seen.insert(normalize_url(&url), url.clone());
+                unique.push(url);
+            }
+        }
+    }
+
+    (unique, duplicates)
+}
+
+// {"error": ".."} or {"error": .., "message": ".."}, but not {"message": "Token prices updated"}
+fn is_error_only(body: &Value) -> bool {
+    body.as_object().is_some_and(|object| {
+        object
+            .keys()
+            .all(|key| ERROR_FIELDS.contains(&key.as_str()))
+            && object
+                .keys()
+                .any(|key| PLAIN_ERROR_FIELDS.contains(&key.as_str()))
+    })
+}
+
+// An error message in the body that talks about keys or tokens
+fn key_error(body: &Value) -> Option<String> {
+    let object = body.as_object()?;
+    ERROR_FIELDS.iter().find_map(|field| {
+        let text = match object.get(*field)? {
+            Value::String(text) => text.clone(),
+            value => value.to_string(),
+        };
+        let lowercase = text.to_lowercase();
+        KEY_ERROR_KEYWORDS
+            .iter()
+            .any(|keyword| lowercase.contains(keyword))
+            .then_some(text)
+    })
+}
+
+// Verdict for one answer, with the parsed body when the url is usable
+pub fn judge_response(url: &str, response: &FetchedResponse) -> (UrlVerdict, Option<Value>) {
+    let body: Option<Value> = serde_json::from_str(&response.body).ok();
+    let content_type = response
+        .headers
+        .get("content-type")
+        .map(|content_type| content_type.to_lowercase())
+        .unwrap_or_default();
+    let is_json_content_type = content_type.contains("json");
+
+    // A 2xx answer only counts as an error when the error is all it says
+    let key_message = body
+        .as_ref()
+        .filter(|body| !(200..=299).contains(&response.status) || is_error_only(body))
+        .and_then(key_error);
+
+    let (kind, reason) = match (response.status, key_message) {
+        (401 | 403, _) => (
+            VerdictKind::AuthRequired,
+            format!("returned status {}, the api needs a key", response.status),
+        ),
+        (_, Some(message)) => (
+            VerdictKind::AuthRequired,
+            format!("error body asks for a key: {}", message),
+        ),
+        (200..=299, _) => match (&body, is_json_content_type) {
+            (Some(_), true) => (VerdictKind::Usable, String::from("JSON response")),
+            (Some(Value::Array(_) | Value::Object(_)), false) => (
+                VerdictKind::Usable,
+                format!("JSON body served as {:?}", content_type),
+            ),
+            (_, true) => (
+                VerdictKind::NotJson,
+                String::from("content type is JSON but the body does not parse"),
+            ),
+            (_, false) => (
+                VerdictKind::NotJson,
+                format!("content type {:?} is not JSON", content_type),
+            ),
+        },
+        (status, _) => (
+            VerdictKind::BadStatus,
+            format!("returned status {}", status),
+        ),
+    };
+
+    let reason = match &response.final_url {
+        Some(final_url) => format!("{} after redirect to {}", reason, final_url),
+        None => reason,
+    };
+    let verdict = UrlVerdict {
+        url: url.to_string(),
+        kind,
+        status: Some(response.status),
+        final_url: response.final_url.clone(),
+        reason,
+    };
+
+    let body = if verdict.is_usable() { body } else { None };
+    (verdict, body)
+}
+
+// Fetch (redirects are followed by the client) and judge one url
+pub async fn validate_url(fetcher: &UrlFetcher, url: &str) -> (UrlVerdict, Option<Value>) {
+    match fetcher.fetch(url).await {
+        Ok(response) => judge_response(url, &response),
+        Err(e) => (
+            UrlVerdict {
+                url: url.to_string(),
+                kind: VerdictKind::Unreachable,
+                status: None,
+                final_url: None,
+                reason: e.to_string(),
+            },
+            None,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn response(status: u16, content_type: &str, body: &str) -> FetchedResponse {
+        FetchedResponse {
+            url: String::from("https://api.example.com/prices"),
+            status,
+            headers: BTreeMap::from([(String::from("content-type"), String::from(content_type))]),
+            body: String::from(body),
+            final_url: None,
+        }
+    }
+
+    #[test]
+    fn test_dedupe_urls() {
+        let urls: Vec<String> = [
+            "https://api.example.com/prices",
+            "HTTPS://API.example.com/prices/",
+            "https://api.example.com/prices?coin=btc",
+        ]
+        .iter()
+        .map(|url| url.to_string())
+        .collect();
+
+        let (unique, duplicates) = dedupe_urls(&urls);
+
+        assert_eq!(unique, vec![urls[0].clone(), urls[2].clone()]);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].kind, VerdictKind::Duplicate);
+        assert_eq!(
+            duplicates[0].reason,
+            "duplicate of https://api.example.com/prices"
+        );
+    }
+
+    #[test]
+    fn test_judge_response() {
+        let url = "https://api.example.com/prices";
+        let kind = |response: FetchedResponse| judge_response(url, &response).0.kind;
+
+        assert_eq!(
+            kind(response(200, "application/json; charset=utf-8", "[1]")),
+            VerdictKind::Usable
+        );
+        assert_eq!(
+            kind(response(200, "text/plain", "{\"a\":1}")),
+            VerdictKind::Usable
+        );
+        assert_eq!(
+            kind(response(200, "text/html", "<html></html>")),
+            VerdictKind::NotJson
+        );
+        assert_eq!(
+            kind(response(401, "application/json", "{}")),
+            VerdictKind::AuthRequired
+        );
+        assert_eq!(
+            kind(response(
+                200,
+                "application/json",
+                "{\"error\":\"Invalid API key\"}"
+            )),
+            VerdictKind::AuthRequired
+        );
+        assert_eq!(
+            kind(response(
+                400,
+                "application/json",
+                "{\"message\":\"missing access_key\"}"
+            )),
+            VerdictKind::AuthRequired
+        );
+        assert_eq!(
+            kind(response(404, "application/json", "{}")),
+            VerdictKind::BadStatus
+        );
+
+        // Successful answers that merely mention tokens or authentication
+        assert_eq!(
+            kind(response(
+                200,
+                "application/json",
+                "{\"message\":\"Token prices updated\",\"prices\":[1]}"
+            )),
+            VerdictKind::Usable
+        );
+        assert_eq!(
+            kind(response(
+                200,
+                "application/json",
+                "{\"message\":\"Token prices updated\"}"
+            )),
+            VerdictKind::Usable
+        );
+        assert_eq!(
+            kind(response(
+                200,
+                "application/json",
+                "{\"detail\":\"authentication optional\"}"
+            )),
+            VerdictKind::Usable
+        );
+
+        let mut redirected = response(200, "application/json", "{\"token\":\"abc\"}");
+        redirected.final_url = Some(String::from("https://api.example.com/v2/prices"));
+        let (verdict, body) = judge_response(url, &redirected);
+        assert!(verdict.is_usable());
+        assert!(body.is_some());
+        assert_eq!(
+            verdict.reason,
+            "JSON response after redirect to https://api.example.com/v2/prices"
+        );
+    }
+}
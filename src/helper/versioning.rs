@@ -0,0 +1,213 @@
+// import
+use std::{fs, path::Path, process::Command};
+
+// const
+const GIT_USER_NAME: &str = "user.name=Plotto";
+const GIT_USER_EMAIL: &str = "user.email=plotto@localhost";
+const WORKSPACE_GITIGNORE: &str = "target/\n";
+
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub agent: String,
+    pub tasks: Vec<String>,
+    pub outcome: String,
+    pub is_green: bool,
+}
+
+impl Revision {
+    pub fn get_message(&self) -> String {
+        let tasks = if self.tasks.is_empty() {
+            String::from("none")
+        } else {
+            self.tasks.join(", ")
+        };
+
+        format!(
+            "{}: {}\n\nAgent: {}\nTask: {}\nOutcome: {}\nGreen: {}\n",
+            self.agent,
+            tasks,
+            self.agent,
+            tasks,
+            self.outcome,
+            if self.is_green { "yes" } else { "no" }
+        )
+    }
+}
+
+// Run git inside the workspace, returns trimmed stdout on success
+fn git(project_path: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(["-c", GIT_USER_NAME, "-c", GIT_USER_EMAIL])
+        .args(args)
+        .current_dir(project_path)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Make the generated workspace a git repository with a baseline commit
+pub fn init_workspace_repo(project_path: &str) -> bool {
+    if !Path::new(project_path).join(".git").exists() {
+        if git(project_path, &["init", "-q"]).is_none() {
+            return false;
+        }
+
+        let gitignore = Path::new(project_path).join(".gitignore");
+        if !gitignore.exists() && fs::write(gitignore, WORKSPACE_GITIGNORE).is_err() {
+            return false;
+        }
+    }
+
+    // Baseline only when there is no history yet
+    if git(project_path, &["rev-parse", "--verify", "HEAD"]).is_none() {
+        let baseline = Revision {
+            agent: String::from("Plotto"),
+            tasks: vec![String::from("initialize workspace")],
+            outcome: String::from("not built"),
+            is_green: false,
+        };
+        return commit_revision(project_path, &baseline).is_some();
+    }
+
+    true
+}
+
+// Commit the whole workspace, returns the short hash of the new revision
+pub fn commit_revision(project_path: &str, revision: &Revision) -> Option<String> {
+    git(project_path, &["add", "-A"])?;
+    git(
+        project_path,
+        &[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            revision.get_message().as_str(),
+        ],
+    )?;
+
+    git(project_path, &["rev-parse", "--short", "HEAD"])
+}
+
+pub fn last_green_revision(project_path: &str) -> Option<String> {
+    let rev = git(
+        project_path,
+        &[
+            "log",
+            "-1",
+            "--format=%h",
+            "--grep=^Green: yes$",
+            "--extended-regexp",
+        ],
+    )?;
+
+    if rev.is_empty() {
+        None
+    } else {
+        Some(rev)
+    }
+}
+
+// Restore the workspace tree at rev as a new commit, so nothing is lost
+pub fn rollback_workspace(project_path: &str, rev: &str) -> Option<String> {
+    let target = git(
+        project_path,
+        &[
+            "rev-parse",
+            "--short",
+            "--verify",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )?;
+    let target_message = git(project_path, &["log", "-1", "--format=%B", &target])?;
+
+    git(project_path, &["read-tree", "-u", "--reset", &target])?;
+
+    // Carry over the outcome of the revision we went back to
+    let outcome = target_message
+        .lines()
+        .find_map(|line| line.strip_prefix("Outcome: "))
+        .unwrap_or("not built")
+        .to_string();
+    let is_green = target_message.lines().any(|line| line == "Green: yes");
+
+    let revision = Revision {
+        agent: String::from("Plotto"),
+        tasks: vec![format!("rollback to {}", target)],
+        outcome,
+        is_green,
+    };
+
+    commit_revision(project_path, &revision)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_revision_message() {
+        let revision = Revision {
+            agent: String::from("Backend Developer"),
+            tasks: vec![
+                String::from("rustc_suggestions"),
+                String::from("print_fixed_code_edits"),
+            ],
+            outcome: String::from("failed at cargo clippy -D warnings"),
+            is_green: true,
+        };
+
+        assert_eq!(
+            revision.get_message(),
+            "Backend Developer: rustc_suggestions, print_fixed_code_edits\n\nAgent: Backend Developer\nTask: rustc_suggestions, print_fixed_code_edits\nOutcome: failed at cargo clippy -D warnings\nGreen: yes\n"
+        );
+    }
+
+    #[test]
+    fn test_rollback_to_last_green_revision() {
+        let workspace = env::temp_dir().join(format!("plotto_versioning_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&workspace);
+        fs::create_dir_all(workspace.join("src")).unwrap();
+        let project_path = workspace.to_str().unwrap();
+        let main_path = workspace.join("src/main.rs");
+
+        fs::write(&main_path, "fn main() {}\n").unwrap();
+        assert!(init_workspace_repo(project_path));
+        assert_eq!(last_green_revision(project_path), None);
+
+        let green = Revision {
+            agent: String::from("Backend Developer"),
+            tasks: vec![String::from("print_backend_webserver_code")],
+            outcome: String::from("passed"),
+            is_green: true,
+        };
+        let green_rev = commit_revision(project_path, &green).unwrap();
+
+        fs::write(&main_path, "fn main() { broken }\n").unwrap();
+        fs::write(workspace.join("src/extra.rs"), "// extra\n").unwrap();
+        let broken = Revision {
+            agent: String::from("Backend Developer"),
+            tasks: vec![String::from("print_fixed_code")],
+            outcome: String::from("failed at cargo build"),
+            is_green: false,
+        };
+        commit_revision(project_path, &broken).unwrap();
+
+        assert_eq!(last_green_revision(project_path), Some(green_rev.clone()));
+        assert!(rollback_workspace(project_path, &green_rev).is_some());
+        assert_eq!(fs::read_to_string(&main_path).unwrap(), "fn main() {}\n");
+        assert!(!workspace.join("src/extra.rs").exists());
+
+        // The rollback commit itself is green
+        assert_ne!(last_green_revision(project_path), Some(green_rev));
+
+        let _ = fs::remove_dir_all(&workspace);
+    }
+}
@@ -1,6 +1,17 @@
-use helper::command_line::get_user_input;
+use std::{env, process};
 
-use crate::models::agent::manager::Manager;
+use helper::{
+    bench::{print_bench_report, run_bench, save_bench_report, BenchConfig},
+    command_line::{get_user_input, PlottoCommand},
+    general::{create_run_dir, read_api_endpoint, WEB_SERVER_PROJECT_PATH},
+    server::{launch_server, ServerConfig},
+    versioning::rollback_workspace,
+};
+
+use crate::models::agent::{
+    manager::Manager,
+    pro::{pro_trait::RouteObject, project_spec::ProjectSpec},
+};
 
 mod apis;
 mod helper;
@@ -10,15 +21,75 @@ mod utils;
 
 #[tokio::main]
 async fn main() {
-    let user_input = get_user_input("What are we building today?");
-    let mut manager = Manager::new(user_input).await;
-    manager.execute().await;
-    println!("Done work!!!");
+    let args: Vec<String> = env::args().collect();
+    let command = PlottoCommand::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
+    match command {
+        PlottoCommand::New(spec_path) => {
+            let mut manager = match spec_path {
+                Some(spec_path) => {
+                    let spec = ProjectSpec::from_file(&spec_path).unwrap_or_else(|error| {
+                        eprintln!("{}", error);
+                        process::exit(1);
+                    });
+                    Manager::from_spec(spec).await
+                }
+                None => Manager::new(get_user_input("What are we building today?")).await,
+            };
+            manager.execute().await;
+            println!("Done work!!!");
+        }
+        PlottoCommand::Rollback(rev) => match rollback_workspace(WEB_SERVER_PROJECT_PATH, &rev) {
+            Some(new_rev) => println!("Rolled back to {} as {}", rev, new_rev),
+            None => {
+                eprintln!("Failed to roll back to {}", rev);
+                process::exit(1);
+            }
+        },
+        PlottoCommand::Bench(config) => {
+            if let Err(error) = bench(&config).await {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+// Load test the GET endpoints of the last generated server, launching it unless --url is given
+async fn bench(config: &BenchConfig) -> Result<(), String> {
+    let routes: Vec<RouteObject> = read_api_endpoint()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or("No api endpoint schema yet, run plotto new first")?;
+
+    let run_dir = create_run_dir();
+    let (base_url, server) = match &config.base_url {
+        Some(base_url) => (base_url.clone(), None),
+        None => {
+            let server =
+                launch_server(WEB_SERVER_PROJECT_PATH, &ServerConfig::from_env(), &run_dir)
+                    .await
+                    .map_err(|error| error.to_string())?;
+            (server.base_url(), Some(server))
+        }
+    };
+
+    let report = run_bench(&base_url, &routes, config).await;
+    if let Some(mut server) = server {
+        server.stop().await;
+    }
+
+    print_bench_report(&report);
+    let path = save_bench_report(&run_dir, &report).map_err(|error| error.to_string())?;
+    println!("Bench report saved to {}", path.display());
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use lib::function_to_string;
+    use lib::{ai_function, function_to_string};
 
     #[function_to_string]
     fn this_is_test(_param: &str) {
@@ -26,9 +97,28 @@ mod tests {
         println!("{}", OUTPUT);
     }
 
+    #[ai_function(output = Vec<String>)]
+    fn this_is_typed_test(_param: &str) {
+        /// Comment in this typed test
+        println!("{}", OUTPUT);
+    }
+
     #[test]
     fn test_function_to_string() {
         let fn_str = this_is_test("Blah blah blah...");
         println!("{:#?}", fn_str);
+        assert_eq!(
+            fn_str,
+            "fn this_is_test(_param: &str) {\n    /// Comment in this test\n    println!(\"{}\", OUTPUT);\n}"
+        );
+    }
+
+    #[test]
+    fn test_ai_function() {
+        // The prompt function is kept, the typed wrapper is generated next to it
+        let prompt: fn(&str) -> &'static str = this_is_typed_test;
+        assert!(prompt("Blah blah blah...").contains("Comment in this typed test"));
+
+        let _wrapper = ai_this_is_typed_test;
     }
 }
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, time::Duration};
 
 use tokio::time;
 
@@ -8,6 +8,7 @@ use super::pro::{
     agent_analyst::AgentAnalyst,
     agent_backend::AgentBackend,
     pro_trait::{GeneralAgent, TaskList},
+    project_spec::ProjectSpec,
 };
 
 pub struct Manager {
@@ -17,6 +18,30 @@ pub struct Manager {
 
 impl Manager {
     pub async fn new(user_input: String) -> Self {
+        let description = Self::convert_to_goal(user_input).await;
+
+        Self::with_tasklist(Self::new_tasklist(description))
+    }
+
+    // The goal keeps the written requirements, and what the spec structures is known up front
+    pub async fn from_spec(spec: ProjectSpec) -> Self {
+        let goal = Self::convert_to_goal(spec.description.clone()).await;
+
+        let mut tasklist = Self::new_tasklist(format!(
+            "{}\nREQUIREMENTS:\n{}",
+            goal,
+            spec.description.trim()
+        ));
+        tasklist.project_scope = Some(spec.prefilled_scope());
+        if !spec.external_apis.is_empty() {
+            tasklist.external_urls = Some(spec.external_apis.clone());
+        }
+        tasklist.spec = Some(spec);
+
+        Self::with_tasklist(tasklist)
+    }
+
+    async fn convert_to_goal(user_input: String) -> String {
         let description = ai_task_request(
             user_input,
             "Manager",
@@ -27,14 +52,24 @@ impl Manager {
 
         println!("{}", description);
 
-        let tasklist: TaskList = TaskList {
+        description
+    }
+
+    fn new_tasklist(description: String) -> TaskList {
+        TaskList {
             description,
             project_scope: None,
             external_urls: None,
-            backend_code: None,
+            external_api_samples: None,
+            url_verdicts: None,
+            spec: None,
+            requirements: None,
+            backend_files: BTreeMap::new(),
             api_endpoint_schema: None,
-        };
+        }
+    }
 
+    fn with_tasklist(tasklist: TaskList) -> Self {
         let agents: Vec<Box<dyn GeneralAgent>> = vec![];
 
         Self { tasklist, agents }
@@ -2,20 +2,33 @@ use std::time::Duration;
 
 use crate::{
     helper::{
-        command_line::AgentCommand,
-        general::{ai_task_request, check_status_code},
+        command_line::{get_user_input, AgentCommand},
+        fixtures::{FetchMode, UrlFetcher},
+        structured::StructuredError,
+        url_validation::{dedupe_urls, validate_url},
     },
     models::agent::basic::{
         basic_agent::{AgentState, BasicAgent},
         basic_trait::BasicTrait,
     },
-    tasks::analyst::{print_project_scope, print_site_urls},
+    tasks::analyst::{
+        ai_print_clarifying_questions, ai_print_project_scope, ai_print_requirements,
+        ai_print_site_urls,
+    },
 };
 use async_trait::async_trait;
 use crossterm::style::Stylize;
 use reqwest::Client;
 
-use super::pro_trait::{GeneralAgent, ProjectScope, TaskList};
+use super::{
+    api_sample::ApiSample,
+    clarification::{
+        answer_questions, default_questions, fold_into_goal, keep_questions, Clarification,
+        ClarifyConfig,
+    },
+    pro_trait::{GeneralAgent, ProjectScope, TaskList},
+    traceability::{number_requirements, numbered_requirements, Requirement},
+};
 
 #[derive(Debug)]
 pub struct AgentAnalyst {
@@ -32,39 +45,144 @@ impl AgentAnalyst {
         Self { attributes }
     }
 
-    // Define project scope base on print_project_scope task in prompt
-    pub async fn define_project_scope(&mut self, tasklist: &mut TaskList) -> ProjectScope {
-        let msg = format!("{}", tasklist.description);
-        let gpt_response = ai_task_request(
-            msg,
+    // Ask up to max_questions about what the goal leaves open and fold the answers into it
+    pub async fn clarify_requirements(
+        &mut self,
+        tasklist: &mut TaskList,
+        config: &ClarifyConfig,
+    ) -> Vec<Clarification> {
+        if config.max_questions == 0 {
+            return vec![];
+        }
+
+        let clarifications = if config.is_interactive {
+            let questions = match ai_print_clarifying_questions(
+                &tasklist.description,
+                &self.attributes.position,
+                "Print clarifying questions",
+            )
+            .await
+            {
+                Ok(questions) => keep_questions(questions, config.max_questions),
+                Err(error) => {
+                    AgentCommand::Issue.print_agent_message(
+                        &self.attributes.position,
+                        &format!("Failed to decode questions, asking the defaults: {}", error),
+                    );
+                    default_questions(config.max_questions)
+                }
+            };
+            answer_questions(&questions, |question| {
+                get_user_input(&format!(
+                    "{} (press enter for: {})",
+                    question.question,
+                    question.default_answer()
+                ))
+            })
+        } else {
+            AgentCommand::Info.print_agent_message(
+                &self.attributes.position,
+                "Not interactive, assuming the default answers",
+            );
+            answer_questions(&default_questions(config.max_questions), |_| String::new())
+        };
+
+        tasklist.description = fold_into_goal(&tasklist.description, &clarifications);
+        clarifications
+    }
+
+    // Define project scope base on print_project_scope task in prompt, re-asking while it is invalid
+    // With a spec, only what the spec leaves open comes from the answer
+    pub async fn define_project_scope(
+        &mut self,
+        tasklist: &mut TaskList,
+    ) -> Result<ProjectScope, StructuredError> {
+        let msg = match &tasklist.spec {
+            Some(spec) => format!(
+                "{}\nKNOWN SCOPE (keep every value, only fill what is empty): {}",
+                tasklist.description,
+                serde_json::to_string(&spec.prefilled_scope())
+                    .expect("Failed to encode project scope")
+            ),
+            None => tasklist.description.clone(),
+        };
+        let merge = |generated: ProjectScope| match &tasklist.spec {
+            Some(spec) => spec.fill_gaps(generated),
+            None => generated,
+        };
+
+        let generated: ProjectScope = ai_print_project_scope(
+            &msg,
             &self.attributes.position,
             "Print project scope",
-            print_project_scope,
+            |generated: &ProjectScope| match merge(generated.clone()).validate() {
+                Ok(()) => vec![],
+                Err(error) => error.problems,
+            },
         )
-        .await;
-        // println!("DEBUG::{}", gpt_response);
-        let project_scope: ProjectScope = serde_json::from_str(gpt_response.as_str())
-            .expect("Failed to decode gpt response from serde_json (project_scope)");
+        .await?;
+        let project_scope = merge(generated);
+
         tasklist.project_scope = Some(project_scope.clone());
         self.attributes.update_state(AgentState::Done);
 
         println!("{:#?}", project_scope);
 
-        project_scope
+        Ok(project_scope)
+    }
+
+    // Numbered requirements come from the spec when it numbers them, otherwise from the goal
+    pub async fn define_requirements(&mut self, tasklist: &mut TaskList) -> Vec<Requirement> {
+        let from_spec = tasklist
+            .spec
+            .as_ref()
+            .map(|spec| numbered_requirements(&spec.description))
+            .unwrap_or_default();
+
+        let requirements = if !from_spec.is_empty() {
+            from_spec
+        } else {
+            match ai_print_requirements(
+                &tasklist.description,
+                &self.attributes.position,
+                "Print numbered requirements",
+            )
+            .await
+            {
+                Ok(texts) => number_requirements(&texts),
+                Err(error) => {
+                    AgentCommand::Issue.print_agent_message(
+                        &self.attributes.position,
+                        &format!("Failed to decode requirements, using the goal: {}", error),
+                    );
+                    number_requirements(std::slice::from_ref(&tasklist.description))
+                }
+            }
+        };
+
+        for requirement in &requirements {
+            AgentCommand::Info.print_agent_message(
+                &self.attributes.position,
+                &format!("{}: {}", requirement.id, requirement.text),
+            );
+        }
+
+        tasklist.requirements = Some(requirements.clone());
+        requirements
     }
 
     // Determine external urls base on print_site_urls task in prompt
     pub async fn determine_external_urls(&mut self, tasklist: &mut TaskList, msg: String) {
-        let gpt_response = ai_task_request(
-            msg,
-            &self.attributes.position,
-            "Print external site urls",
-            print_site_urls,
-        )
-        .await;
-        // println!("DEBUG::{}", gpt_response);
-        let external_urls: Vec<String> = serde_json::from_str(gpt_response.as_str())
-            .expect("Failed to decode gpt response to serde_json (external_urls)");
+        let external_urls: Vec<String> =
+            ai_print_site_urls(&msg, &self.attributes.position, "Print external site urls")
+                .await
+                .unwrap_or_else(|error| {
+                    AgentCommand::Issue.print_agent_message(
+                        &self.attributes.position,
+                        &format!("No external urls: {}", error),
+                    );
+                    vec![]
+                });
 
         println!("{:#?}", external_urls);
 
@@ -83,67 +201,93 @@ impl GeneralAgent for AgentAnalyst {
         while self.attributes.state != AgentState::Done {
             match self.attributes.state {
                 AgentState::Planning => {
-                    let project_scope: ProjectScope = self.define_project_scope(tasklist).await;
+                    let clarifications = self
+                        .clarify_requirements(tasklist, &ClarifyConfig::from_env())
+                        .await;
+                    let project_scope: ProjectScope = self.define_project_scope(tasklist).await?;
+                    if let Some(project_scope) = tasklist.project_scope.as_mut() {
+                        project_scope.clarifications = clarifications;
+                    }
+                    self.define_requirements(tasklist).await;
 
-                    // Check project scope has external urls
+                    // Check project scope has external urls, the ones from a spec are only tested
                     if project_scope.is_external_urls_required {
-                        self.determine_external_urls(tasklist, tasklist.description.clone())
-                            .await;
+                        if tasklist.external_urls.is_none() {
+                            self.determine_external_urls(tasklist, tasklist.description.clone())
+                                .await;
+                        }
                         self.attributes.state = AgentState::Testing;
                     }
                 }
                 AgentState::Testing => {
-                    // Exclude urls require api key
-                    let mut exclude_urls: Vec<String> = vec![];
+                    let mut samples: Vec<ApiSample> = vec![];
 
-                    // Create request to disconnect after 5 seconds
+                    // Create request to disconnect after 5 seconds, redirects are followed
                     let client: Client = Client::builder()
                         .timeout(Duration::from_secs(5))
                         .build()
                         .unwrap();
+                    let fetcher = UrlFetcher::from_env(client);
+                    if fetcher.mode != FetchMode::Live {
+                        AgentCommand::Info.print_agent_message(
+                            &self.attributes.position,
+                            format!(
+                                "Checking urls in {:?} mode, fixtures in {}",
+                                fetcher.mode,
+                                fetcher.fixtures_dir.display()
+                            )
+                            .as_str(),
+                        );
+                    }
 
                     let urls = tasklist
                         .external_urls
                         .as_ref()
                         .expect("No URLs in task list");
+                    let (urls, mut verdicts) = dedupe_urls(urls);
 
-                    // Push url not working to exclude_urls
-                    for url in urls {
+                    for url in &urls {
                         // Print agent command
                         let endpoint_str = format!("Testing URL Endpoint: {}", url);
                         AgentCommand::Test
                             .print_agent_message(&self.attributes.position, &endpoint_str);
 
-                        // Test status url
-                        let status_code = check_status_code(&client, url).await;
-                        match status_code {
-                            Ok(code) => {
-                                if code != 200 {
-                                    exclude_urls.push(url.clone());
-                                    println!("{} ❌", format!("Fail::{}", 404).red().bold());
-                                } else {
-                                    println!("{} ✅", format!("Pass::{}", 200).green().bold());
-                                }
-                            }
-                            Err(error) => {
-                                println!("Error checking {}", error);
-                            }
+                        let (verdict, body) = validate_url(&fetcher, url).await;
+                        let status = verdict
+                            .status
+                            .map_or(String::from("---"), |status| status.to_string());
+                        if verdict.is_usable() {
+                            println!(
+                                "{} ✅ {}",
+                                format!("Pass::{}", status).green().bold(),
+                                verdict.reason
+                            );
+                        } else {
+                            println!(
+                                "{} ❌ {:?}: {}",
+                                format!("Fail::{}", status).red().bold(),
+                                verdict.kind,
+                                verdict.reason
+                            );
+                        }
+
+                        // Keep a sample of the JSON to write structs against
+                        if let Some(body) = body {
+                            samples.push(ApiSample::from_response(url, &body));
                         }
+                        verdicts.push(verdict);
                     }
 
-                    // Exclude any faulty urls
-                    if exclude_urls.len() > 0 {
-                        let new_urls: Vec<String> = tasklist
-                            .external_urls
-                            .as_ref()
-                            .unwrap()
+                    // Only usable urls move on to the backend
+                    tasklist.external_urls = Some(
+                        verdicts
                             .iter()
-                            .filter(|url| !exclude_urls.contains(url))
-                            .cloned()
-                            .collect();
-
-                        tasklist.external_urls = Some(new_urls);
-                    }
+                            .filter(|verdict| verdict.is_usable())
+                            .map(|verdict| verdict.url.clone())
+                            .collect(),
+                    );
+                    tasklist.external_api_samples = Some(samples);
+                    tasklist.url_verdicts = Some(verdicts);
 
                     self.attributes.state = AgentState::Done;
                 }
@@ -157,6 +301,8 @@ impl GeneralAgent for AgentAnalyst {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use super::*;
 
     #[test]
@@ -171,12 +317,19 @@ mod tests {
             description: String::from("build a website that manages task lists"),
             project_scope: None,
             external_urls: None,
-            backend_code: None,
+            external_api_samples: None,
+            url_verdicts: None,
+            spec: None,
+            requirements: None,
+            backend_files: BTreeMap::new(),
             api_endpoint_schema: None,
         };
 
         let mut agent_analyst = AgentAnalyst::new();
-        let project_scope: ProjectScope = agent_analyst.define_project_scope(&mut tasklist).await;
+        let project_scope: ProjectScope = agent_analyst
+            .define_project_scope(&mut tasklist)
+            .await
+            .expect("Failed to define project scope");
         println!("{:#?}", project_scope);
     }
 
@@ -188,9 +341,14 @@ mod tests {
                 is_crud_required: true,
                 is_user_login_and_logout: true,
                 is_external_urls_required: true,
+                ..ProjectScope::default()
             }),
             external_urls: None,
-            backend_code: None,
+            external_api_samples: None,
+            url_verdicts: None,
+            spec: None,
+            requirements: None,
+            backend_files: BTreeMap::new(),
             api_endpoint_schema: None,
         };
         let mut agent_analyst = AgentAnalyst::new();
@@ -219,7 +377,11 @@ mod tests {
             description: String::from("build a website that tracks forex and crypto prices"),
             project_scope: None,
             external_urls: None,
-            backend_code: None,
+            external_api_samples: None,
+            url_verdicts: None,
+            spec: None,
+            requirements: None,
+            backend_files: BTreeMap::new(),
             api_endpoint_schema: None,
         };
 
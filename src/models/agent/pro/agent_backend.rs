@@ -1,18 +1,32 @@
-use std::{
-    process::{Command, Stdio},
-    time::Duration,
-};
+use std::{collections::HashMap, env, path::PathBuf, time::Duration};
 
 use async_trait::async_trait;
 use reqwest::Client;
-use tokio::time;
 
 use crate::{
     helper::{
+        api_tests::{write_api_tests, API_TESTS_PATH},
         command_line::{confirm_safe_code, AgentCommand},
+        compiler::{
+            apply_machine_applicable_fixes, run_stage, BuildOutput, Diagnostic, QualityGate,
+            QualityStage,
+        },
+        endpoints::{
+            plan_endpoint_tests, run_endpoint_tests, schema_mismatch_report, EndpointResult,
+        },
         general::{
-            ai_task_request, check_status_code, read_code_template, read_exec_main_code,
-            save_api_endpoint, save_backend_code, WEB_SERVER_PROJECT_PATH,
+            ai_task_request, create_run_dir, read_code_template, save_api_endpoint, OPENAPI_PATH,
+            WEB_SERVER_PROJECT_PATH,
+        },
+        manifest::{
+            read_project_files, render_project_files, write_manifest, ManifestFile, ProjectManifest,
+        },
+        openapi::{build_openapi_document, save_openapi_document},
+        patch::apply_project_edits,
+        routes::extract_routes,
+        server::{launch_server, read_log_tail, ServerConfig},
+        versioning::{
+            commit_revision, init_workspace_repo, last_green_revision, rollback_workspace, Revision,
         },
     },
     models::agent::basic::{
@@ -20,18 +34,91 @@ use crate::{
         basic_trait::BasicTrait,
     },
     tasks::{
-        backend::{print_backend_webserver_code, print_fixed_code, print_improved_webserver_code},
-        tester::print_rest_api_endpoints,
+        backend::{
+            print_backend_webserver_code, print_fixed_code, print_fixed_code_edits,
+            print_improved_webserver_code, print_improved_webserver_code_edits,
+        },
+        tester::ai_print_rest_api_endpoints,
     },
 };
 
-use super::pro_trait::{GeneralAgent, RouteObject, TaskList};
+use super::{
+    api_sample::render_api_samples,
+    pro_trait::{GeneralAgent, RouteObject, TaskList},
+    traceability::{
+        build_traceability, print_traceability_report, render_requirements,
+        save_traceability_report,
+    },
+};
+
+// const
+const MAX_AUTO_FIX_ROUNDS: u8 = 3;
+const MAX_ENDPOINT_FIX_ROUNDS: u8 = 2;
+
+// Data model and requirements from the analyst, empty when there is no scope
+fn project_scope_prompt(tasklist: &TaskList) -> String {
+    match &tasklist.project_scope {
+        Some(project_scope) => format!(
+            "PROJECT SCOPE: {} \n",
+            serde_json::to_string(project_scope).expect("Failed to encode project scope")
+        ),
+        None => String::new(),
+    }
+}
+
+// Numbered requirements the handlers link back to with their doc comments
+fn requirements_prompt(tasklist: &TaskList) -> String {
+    match &tasklist.requirements {
+        Some(requirements) if !requirements.is_empty() => {
+            format!("REQUIREMENTS:\n{} \n", render_requirements(requirements))
+        }
+        _ => String::new(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EditMode {
+    Patch,
+    Rewrite,
+}
+
+impl EditMode {
+    // PLOTTO_EDIT_MODE=rewrite asks for whole files instead of patches
+    pub fn from_env() -> Self {
+        match env::var("PLOTTO_EDIT_MODE") {
+            Ok(mode) if mode.eq_ignore_ascii_case("rewrite") => EditMode::Rewrite,
+            _ => EditMode::Patch,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GateOutcome {
+    Passed,
+    Failed,
+    Exhausted(QualityStage),
+}
 
 #[derive(Debug)]
 pub struct AgentBackend {
     pub attributes: BasicAgent,
+    pub edit_mode: EditMode,
+    pub quality_gate: QualityGate,
+    pub failed_stage: Option<QualityStage>,
+    pub stage_retries: HashMap<QualityStage, u8>,
+    pub stage_results: Vec<(QualityStage, bool)>,
     pub bug_errors: Option<String>,
+    pub bug_diagnostics: Vec<Diagnostic>,
     pub bug_count: u8,
+    pub auto_fixed_count: usize,
+    pub llm_fixed_count: usize,
+    pub escalated_error_count: usize,
+    pub pending_tasks: Vec<String>,
+    pub last_progress: Option<(usize, usize)>,
+    pub endpoint_results: Vec<EndpointResult>,
+    pub endpoint_fix_rounds: u8,
+    pub server_config: ServerConfig,
+    pub run_dir: Option<PathBuf>,
 }
 
 impl AgentBackend {
@@ -43,19 +130,49 @@ impl AgentBackend {
 
         Self {
             attributes,
+            edit_mode: EditMode::from_env(),
+            quality_gate: QualityGate::from_env(),
+            failed_stage: None,
+            stage_retries: HashMap::new(),
+            stage_results: vec![],
             bug_errors: None,
+            bug_diagnostics: vec![],
             bug_count: 0,
+            auto_fixed_count: 0,
+            llm_fixed_count: 0,
+            escalated_error_count: 0,
+            pending_tasks: vec![],
+            last_progress: None,
+            endpoint_results: vec![],
+            endpoint_fix_rounds: 0,
+            server_config: ServerConfig::from_env(),
+            run_dir: None,
         }
     }
 
     pub async fn initial_backend_code(&mut self, tasklist: &mut TaskList) {
         let code_template = read_code_template();
 
-        let msg: String = format!(
-            "CODE TEMPLATE: {} \n PROJECT DESCRIPTION: {} \n",
-            code_template, tasklist.description
+        let mut msg: String = format!(
+            "CODE TEMPLATE: {} \n PROJECT DESCRIPTION: {} \n{}{}",
+            code_template,
+            tasklist.description,
+            project_scope_prompt(tasklist),
+            requirements_prompt(tasklist)
         );
 
+        // Real responses of the external urls, so structs use their actual fields
+        if let Some(samples) = tasklist
+            .external_api_samples
+            .as_ref()
+            .filter(|samples| !samples.is_empty())
+        {
+            msg.push_str(&format!(
+                "EXTERNAL API SAMPLES: \n{} \n",
+                render_api_samples(samples)
+            ));
+        }
+
         let gpt_response = ai_task_request(
             msg,
             &self.attributes.position,
@@ -65,17 +182,106 @@ impl AgentBackend {
         .await;
         // println!("DEBUG::{}", gpt_response);
 
-        // Save main file
-        save_backend_code(&gpt_response);
-        tasklist.backend_code = Some(gpt_response);
+        // Save project files
+        let manifest = ProjectManifest::from_response(&gpt_response);
+        self.save_manifest(tasklist, &manifest, "print_backend_webserver_code");
+    }
+
+    // Validate and write the manifest, refusing anything outside the workspace
+    pub fn save_manifest(
+        &mut self,
+        tasklist: &mut TaskList,
+        manifest: &ProjectManifest,
+        task: &str,
+    ) -> bool {
+        let is_saved = match write_manifest(WEB_SERVER_PROJECT_PATH, manifest) {
+            Ok(()) => {
+                self.pending_tasks.push(String::from(task));
+                true
+            }
+            Err(error) => {
+                AgentCommand::Issue.print_agent_message(
+                    &self.attributes.position,
+                    format!("Refused project manifest: {}", error).as_str(),
+                );
+                false
+            }
+        };
+
+        tasklist.backend_files = read_project_files(WEB_SERVER_PROJECT_PATH);
+        is_saved
+    }
+
+    // Ask for edits against the current files, false when they do not apply cleanly
+    pub async fn request_code_edits(
+        &mut self,
+        tasklist: &mut TaskList,
+        msg: String,
+        agent_task: &str,
+        func: fn(&str) -> &'static str,
+        task: &str,
+    ) -> bool {
+        let gpt_response = ai_task_request(msg, &self.attributes.position, agent_task, func).await;
+
+        match apply_project_edits(&tasklist.backend_files, &gpt_response) {
+            Ok(patched_files) => {
+                let manifest = ProjectManifest {
+                    files: patched_files
+                        .into_iter()
+                        .map(|(path, contents)| ManifestFile { path, contents })
+                        .collect(),
+                    dependencies: vec![],
+                };
+
+                self.save_manifest(tasklist, &manifest, task)
+            }
+            Err(error) => {
+                AgentCommand::Issue.print_agent_message(
+                    &self.attributes.position,
+                    format!(
+                        "Patch did not apply ({}), falling back to full rewrite...",
+                        error
+                    )
+                    .as_str(),
+                );
+                false
+            }
+        }
     }
 
     pub async fn improve_backend_code(&mut self, tasklist: &mut TaskList) {
-        let backend_code = read_exec_main_code();
+        tasklist.backend_files = read_project_files(WEB_SERVER_PROJECT_PATH);
+        let backend_code = render_project_files(&tasklist.backend_files);
+
+        if self.edit_mode == EditMode::Patch {
+            let msg = format!(
+                "CURRENT CODE:\n{}\n PROJECT DESCRIPTION: {} \n{}{}",
+                backend_code,
+                tasklist.description,
+                project_scope_prompt(tasklist),
+                requirements_prompt(tasklist)
+            );
+
+            if self
+                .request_code_edits(
+                    tasklist,
+                    msg,
+                    "Improve backend code",
+                    print_improved_webserver_code_edits,
+                    "print_improved_webserver_code_edits",
+                )
+                .await
+            {
+                return;
+            }
+        }
 
         let msg = format!(
-            "CODE TEMPLATE: {:?} \n PROJECT DESCRIPTION: {:?} \n",
-            backend_code, tasklist.description
+            "CODE TEMPLATE: {:?} \n PROJECT DESCRIPTION: {:?} \n{}{}",
+            backend_code,
+            tasklist.description,
+            project_scope_prompt(tasklist),
+            requirements_prompt(tasklist)
         );
 
         let gpt_response = ai_task_request(
@@ -88,12 +294,92 @@ impl AgentBackend {
 
         println!("DEBUG::{}", gpt_response);
 
-        save_backend_code(&gpt_response);
-        tasklist.backend_code = Some(gpt_response);
+        let manifest = ProjectManifest::from_response(&gpt_response);
+        self.save_manifest(tasklist, &manifest, "print_improved_webserver_code");
+    }
+
+    // Apply rustc machine-applicable suggestions and rebuild until nothing is left to apply
+    pub fn auto_fix_bug(&mut self) -> Option<BuildOutput> {
+        let mut diagnostics = self.bug_diagnostics.clone();
+        let mut build_output: Option<BuildOutput> = None;
+
+        for _ in 0..MAX_AUTO_FIX_ROUNDS {
+            let applied = apply_machine_applicable_fixes(WEB_SERVER_PROJECT_PATH, &diagnostics);
+            if applied == 0 {
+                break;
+            }
+
+            if build_output.is_none() {
+                self.pending_tasks.push(String::from("rustc_suggestions"));
+            }
+
+            AgentCommand::Test.print_agent_message(
+                &self.attributes.position,
+                format!("Applied {} compiler suggestions, rebuilding...", applied).as_str(),
+            );
+
+            let errors_before = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.level == "error")
+                .count();
+            let rebuild = run_stage(
+                WEB_SERVER_PROJECT_PATH,
+                self.failed_stage.unwrap_or(QualityStage::Build),
+            );
+            self.auto_fixed_count += errors_before.saturating_sub(rebuild.errors().len());
+
+            diagnostics = rebuild.diagnostics.clone();
+            let is_success = rebuild.success;
+            build_output = Some(rebuild);
+
+            if is_success {
+                break;
+            }
+        }
+
+        build_output
     }
 
     pub async fn fix_bug(&mut self, tasklist: &mut TaskList) {
-        let backend_code = read_exec_main_code();
+        // Only escalate to the llm what the compiler could not fix itself
+        if let Some(build_output) = self.auto_fix_bug() {
+            tasklist.backend_files = read_project_files(WEB_SERVER_PROJECT_PATH);
+            if build_output.success {
+                return;
+            }
+
+            self.bug_errors = Some(build_output.error_report());
+            self.bug_diagnostics = build_output.diagnostics.clone();
+        }
+
+        self.escalated_error_count = self
+            .bug_diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == "error")
+            .count();
+
+        tasklist.backend_files = read_project_files(WEB_SERVER_PROJECT_PATH);
+        let backend_code = render_project_files(&tasklist.backend_files);
+
+        if self.edit_mode == EditMode::Patch {
+            let msg = format!(
+                "BROKEN CODE:\n{}\n ERROR BUGS: {:?} \n",
+                backend_code, self.bug_errors
+            );
+
+            if self
+                .request_code_edits(
+                    tasklist,
+                    msg,
+                    "Fix backend code",
+                    print_fixed_code_edits,
+                    "print_fixed_code_edits",
+                )
+                .await
+            {
+                return;
+            }
+        }
 
         let msg = format!(
             "BROKEN CODE: {:?} \n ERROR BUGS: {:?} \n
@@ -111,26 +397,222 @@ impl AgentBackend {
 
         println!("DEBUG::{}", gpt_response);
 
-        save_backend_code(&gpt_response);
-        tasklist.backend_code = Some(gpt_response);
+        let manifest = ProjectManifest::from_response(&gpt_response);
+        self.save_manifest(tasklist, &manifest, "print_fixed_code");
+    }
+
+    // Run every quality stage in order, stopping at the first failure
+    pub fn run_quality_gate(&mut self) -> GateOutcome {
+        self.stage_results.clear();
+
+        for budget in self.quality_gate.stages.clone() {
+            AgentCommand::Test.print_agent_message(
+                &self.attributes.position,
+                format!("Running {}...", budget.stage.get_name()).as_str(),
+            );
+
+            let stage_output = run_stage(WEB_SERVER_PROJECT_PATH, budget.stage);
+
+            // Count errors the llm fixed since this stage was last escalated
+            if self.failed_stage == Some(budget.stage) {
+                self.llm_fixed_count += self
+                    .escalated_error_count
+                    .saturating_sub(stage_output.errors().len());
+                self.escalated_error_count = 0;
+            }
+
+            self.stage_results
+                .push((budget.stage, stage_output.success));
+            if stage_output.success {
+                continue;
+            }
+
+            // Stop when this stage has used up its retry budget
+            let retries = self.stage_retries.entry(budget.stage).or_insert(0);
+            if *retries >= budget.max_retries {
+                return GateOutcome::Exhausted(budget.stage);
+            }
+            *retries += 1;
+
+            // Update error stat
+            self.bug_count += 1;
+            self.failed_stage = Some(budget.stage);
+            self.bug_errors = Some(stage_output.error_report());
+            self.bug_diagnostics = stage_output.diagnostics;
+
+            return GateOutcome::Failed;
+        }
+
+        self.failed_stage = None;
+        GateOutcome::Passed
+    }
+
+    // Commit the code of every agent step with the quality gate outcome
+    pub fn record_revision(&mut self) {
+        let outcome = match self.stage_results.iter().find(|(_, success)| !success) {
+            Some((stage, _)) => format!("failed at {}", stage.get_name()),
+            None => String::from("passed"),
+        };
+        let is_green = self.stage_results.iter().all(|(_, success)| *success)
+            || self.stage_results.contains(&(QualityStage::Build, true));
+
+        let revision = Revision {
+            agent: self.attributes.position.clone(),
+            tasks: self.pending_tasks.drain(..).collect(),
+            outcome,
+            is_green,
+        };
+
+        if let Some(rev) = commit_revision(WEB_SERVER_PROJECT_PATH, &revision) {
+            AgentCommand::Info.print_agent_message(
+                &self.attributes.position,
+                format!("Saved revision {} ({})", rev, revision.outcome).as_str(),
+            );
+        }
+    }
+
+    // A fix is worse when the gate fails at an earlier stage, or with more errors at the same one
+    pub fn is_regression(&mut self) -> bool {
+        let error_count = self
+            .bug_diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == "error")
+            .count();
+        let progress = (self.stage_results.len(), error_count);
+
+        let is_worse = match self.last_progress {
+            Some((stage_reached, errors)) => {
+                progress.0 < stage_reached || (progress.0 == stage_reached && progress.1 > errors)
+            }
+            None => false,
+        };
+
+        self.last_progress = Some(progress);
+        is_worse
+    }
+
+    pub fn print_quality_report(&self) {
+        for budget in &self.quality_gate.stages {
+            let result = self
+                .stage_results
+                .iter()
+                .find(|(stage, _)| *stage == budget.stage)
+                .map(|(_, success)| *success);
+            let retries = self.stage_retries.get(&budget.stage).unwrap_or(&0);
+
+            let (command, status) = match result {
+                Some(true) => (AgentCommand::Info, "passed"),
+                Some(false) => (AgentCommand::Issue, "failed"),
+                None => (AgentCommand::Info, "not run"),
+            };
+
+            command.print_agent_message(
+                &self.attributes.position,
+                format!(
+                    "{}: {} ({}/{} retries used)",
+                    budget.stage.get_name(),
+                    status,
+                    retries,
+                    budget.max_retries
+                )
+                .as_str(),
+            );
+        }
+
+        self.print_fix_report();
+    }
+
+    pub fn print_endpoint_report(&self) {
+        for result in &self.endpoint_results {
+            let status = result
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| String::from("no response"));
+
+            match (result.passed, &result.reason) {
+                (true, _) => AgentCommand::Test.print_agent_message(
+                    &self.attributes.position,
+                    format!("PASS {} {} ({})", result.method, result.route, status).as_str(),
+                ),
+                (false, reason) => AgentCommand::Issue.print_agent_message(
+                    &self.attributes.position,
+                    format!(
+                        "FAIL {} {} ({}): {}",
+                        result.method,
+                        result.route,
+                        result.url,
+                        reason.as_deref().unwrap_or("unknown error")
+                    )
+                    .as_str(),
+                ),
+            }
+        }
+
+        let passed = self
+            .endpoint_results
+            .iter()
+            .filter(|result| result.passed)
+            .count();
+        AgentCommand::Test.print_agent_message(
+            &self.attributes.position,
+            format!(
+                "Endpoints passed: {}/{}",
+                passed,
+                self.endpoint_results.len()
+            )
+            .as_str(),
+        );
+    }
+
+    pub fn print_fix_report(&self) {
+        AgentCommand::Info.print_agent_message(
+            &self.attributes.position,
+            format!(
+                "Compiler errors fixed: {} automatically, {} by LLM",
+                self.auto_fixed_count, self.llm_fixed_count
+            )
+            .as_str(),
+        );
     }
 
-    pub async fn extract_rest_api_endpoints(&mut self) -> String {
-        let backend_code: String = read_exec_main_code();
+    // Extract routes statically with syn, the llm is only asked when none are found
+    pub async fn extract_rest_api_endpoints(
+        &mut self,
+        tasklist: &mut TaskList,
+    ) -> Vec<RouteObject> {
+        tasklist.backend_files = read_project_files(WEB_SERVER_PROJECT_PATH);
+
+        let api_endpoints = extract_routes(&tasklist.backend_files);
+        if !api_endpoints.is_empty() {
+            return api_endpoints;
+        }
+
+        AgentCommand::Issue.print_agent_message(
+            &self.attributes.position,
+            "No routes found statically, asking the LLM...",
+        );
 
         // Structure message context
+        let backend_code: String = render_project_files(&tasklist.backend_files);
         let msg: String = format!("CODE INPUT: {}", backend_code);
 
-        let gpt_response = ai_task_request(
-            msg,
+        ai_print_rest_api_endpoints(
+            &msg,
             &self.attributes.position,
             "Extract rest api endpoints to schemas",
-            print_rest_api_endpoints,
         )
-        .await;
-        println!("DEBUG::{}", gpt_response);
-
-        gpt_response
+        .await
+        .unwrap_or_else(|error| {
+            AgentCommand::Issue.print_agent_message(
+                &self.attributes.position,
+                format!(
+                    "Failed to decode api endpoints: {}\nLast output: {}",
+                    error, error.last_response
+                )
+                .as_str(),
+            );
+            vec![]
+        })
     }
 }
 
@@ -144,6 +626,13 @@ impl GeneralAgent for AgentBackend {
         while self.attributes.state != AgentState::Done {
             match &self.attributes.state {
                 AgentState::Planning => {
+                    if !init_workspace_repo(WEB_SERVER_PROJECT_PATH) {
+                        AgentCommand::Issue.print_agent_message(
+                            &self.attributes.position,
+                            "Git is unavailable, code revisions will not be recorded...",
+                        );
+                    }
+
                     self.initial_backend_code(tasklist).await;
                     self.attributes.state = AgentState::Working;
                     continue;
@@ -169,144 +658,231 @@ impl GeneralAgent for AgentBackend {
                         panic!("Better go work on some AI alignment instead...")
                     }
 
-                    // Build and test code generated
-                    AgentCommand::Test.print_agent_message(
-                        self.attributes.position.as_str(),
-                        "Building project...",
-                    );
+                    // Run the quality gate on code generated
+                    let gate_outcome = self.run_quality_gate();
+                    self.record_revision();
 
-                    // Build code generated
-                    // ???
-                    let build_backend_server = Command::new("cargo")
-                        .arg("build")
-                        .current_dir(WEB_SERVER_PROJECT_PATH)
-                        .stdout(Stdio::inherit())
-                        .stderr(Stdio::inherit())
-                        .output()
-                        .expect("Failed to build backend application");
-
-                    // Determine if build errors
-                    if !build_backend_server.status.success() {
-                        let error_arr = build_backend_server.stderr;
-                        let error_str = String::from_utf8(error_arr).unwrap();
-
-                        // Update error stat
-                        self.bug_count += 1;
-                        self.bug_errors = Some(error_str);
-
-                        // Exit if too many bugs
-                        if self.bug_count > 2 {
+                    match gate_outcome {
+                        GateOutcome::Passed => {}
+                        GateOutcome::Failed => {
+                            // Revert to the last green build when the fix made things worse
+                            if self.is_regression() {
+                                if let Some(rev) = last_green_revision(WEB_SERVER_PROJECT_PATH) {
+                                    AgentCommand::Issue.print_agent_message(
+                                        &self.attributes.position,
+                                        format!(
+                                            "Fix made things worse, reverting to last green build {}...",
+                                            rev
+                                        )
+                                        .as_str(),
+                                    );
+
+                                    if rollback_workspace(WEB_SERVER_PROJECT_PATH, &rev).is_some() {
+                                        tasklist.backend_files =
+                                            read_project_files(WEB_SERVER_PROJECT_PATH);
+                                        self.last_progress = None;
+                                        continue;
+                                    }
+                                }
+                            }
+
+                            // Pass back to rework
+                            self.attributes.state = AgentState::Working;
+                            continue;
+                        }
+                        GateOutcome::Exhausted(stage) => {
                             AgentCommand::Issue.print_agent_message(
                                 &self.attributes.position,
-                                "Too many bugs found in code...",
+                                format!(
+                                    "Too many bugs found in code, {} gave up...",
+                                    stage.get_name()
+                                )
+                                .as_str(),
                             );
-                            panic!("Too many bugs!");
+                            self.print_quality_report();
+                            self.attributes.state = AgentState::Done;
+
+                            return Err(format!(
+                                "Quality gate stage {} ran out of retries",
+                                stage.get_name()
+                            )
+                            .into());
                         }
-
-                        // Pass back to rework
-                        self.attributes.state = AgentState::Working;
-                        continue;
                     }
 
-                    // Build success without errors
+                    // Quality gate passed without errors
                     self.bug_count = 0;
                     AgentCommand::Test.print_agent_message(
                         &self.attributes.position,
-                        "Server is built successful",
+                        "Server passed the quality gate",
                     );
+                    self.print_quality_report();
 
                     // Extract api endpoints
-                    let gpt_response = self.extract_rest_api_endpoints().await;
-
-                    // Convert api endpoints into values
-                    let api_endpoints: Vec<RouteObject> = serde_json::from_str(
-                        gpt_response.as_str(),
-                    )
-                    .expect("Failed to decode gpt response from serde_json (api_endpoints)");
-
-                    // Define "get" and not dynamic endpoints to check
-                    let check_endpoints: Vec<RouteObject> = api_endpoints
-                        .iter()
-                        .filter(|&route_object| {
-                            route_object.method == "get" && route_object.is_route_dynamic == "false"
-                        })
-                        .cloned()
-                        .collect();
+                    let api_endpoints: Vec<RouteObject> =
+                        self.extract_rest_api_endpoints(tasklist).await;
 
                     // Store api endpoints
-                    tasklist.api_endpoint_schema = Some(check_endpoints.clone());
+                    tasklist.api_endpoint_schema = Some(api_endpoints.clone());
 
                     // Build backend application
                     AgentCommand::Test
                         .print_agent_message(&self.attributes.position, "Starting web server...");
 
-                    // Execute running server
-                    let mut run_backend_server = Command::new("cargo")
-                        .arg("run")
-                        .current_dir(WEB_SERVER_PROJECT_PATH)
-                        .stdout(Stdio::inherit())
-                        .stderr(Stdio::inherit())
-                        .spawn()
-                        .expect("Failed to run backend application");
+                    let run_dir = self.run_dir.get_or_insert_with(create_run_dir).clone();
+                    let mut server =
+                        match launch_server(WEB_SERVER_PROJECT_PATH, &self.server_config, &run_dir)
+                            .await
+                        {
+                            Ok(server) => server,
+                            Err(e) => {
+                                let log_tail = read_log_tail(&run_dir.join("server.log"));
+                                AgentCommand::Issue
+                                    .print_agent_message(&self.attributes.position, &e.to_string());
+
+                                // A server that builds but never comes up is a runtime bug
+                                if self.endpoint_fix_rounds < MAX_ENDPOINT_FIX_ROUNDS {
+                                    self.endpoint_fix_rounds += 1;
+                                    self.failed_stage = None;
+                                    self.bug_diagnostics = vec![];
+                                    self.bug_errors =
+                                        Some(format!("{}\nSERVER LOG:\n{}", e, log_tail));
+                                    self.bug_count += 1;
+                                    self.attributes.state = AgentState::Working;
+                                    continue;
+                                }
+
+                                self.attributes.state = AgentState::Done;
+                                return Err(e.into());
+                            }
+                        };
 
-                    // Let user know testing on server will take place soon
                     AgentCommand::Test.print_agent_message(
                         &self.attributes.position,
-                        "Launching test endpoints in 5 seconds...",
+                        format!(
+                            "Web server is ready on {}, logs in {}",
+                            server.base_url(),
+                            server.log_path.display()
+                        )
+                        .as_str(),
+                    );
+
+                    // Exercise every endpoint: auth flow, CRUD sequences, then the rest
+                    let is_login_required = tasklist
+                        .project_scope
+                        .as_ref()
+                        .is_some_and(|scope| scope.is_user_login_and_logout);
+                    let calls = plan_endpoint_tests(&api_endpoints, is_login_required);
+
+                    // Create client request with timout 5s, keeping session cookies from login
+                    let client = Client::builder()
+                        .timeout(Duration::from_secs(5))
+                        .cookie_store(true)
+                        .build()
+                        .unwrap();
+
+                    self.endpoint_results =
+                        run_endpoint_tests(&client, &server.base_url(), &calls).await;
+                    self.print_endpoint_report();
+
+                    save_api_endpoint(
+                        &serde_json::to_string_pretty(&api_endpoints)
+                            .expect("Failed to encode api endpoints"),
                     );
-                    time::sleep(Duration::from_secs(5)).await;
-
-                    // Check status code from test
-                    for endpoint in check_endpoints {
-                        // Print endpoint testing
-                        AgentCommand::Test.print_agent_message(
-                            self.attributes.position.as_str(),
-                            format!("Testing endpoint {}...", endpoint.route).as_str(),
+
+                    // Export the same routes as OpenAPI for frontend clients
+                    let openapi_document =
+                        build_openapi_document(&tasklist.description, &api_endpoints);
+                    if let Err(e) = save_openapi_document(OPENAPI_PATH, &openapi_document) {
+                        AgentCommand::Issue
+                            .print_agent_message(&self.attributes.position, &e.to_string());
+                    }
+
+                    server.stop().await;
+
+                    // Wrong response shapes go back to the developer as bugs
+                    if let Some(report) = schema_mismatch_report(&self.endpoint_results) {
+                        if self.endpoint_fix_rounds < MAX_ENDPOINT_FIX_ROUNDS {
+                            AgentCommand::Issue.print_agent_message(
+                                &self.attributes.position,
+                                "Responses do not match the declared schema, fixing...",
+                            );
+                            self.endpoint_fix_rounds += 1;
+                            self.failed_stage = None;
+                            self.bug_diagnostics = vec![];
+                            self.bug_errors = Some(report);
+                            self.bug_count += 1;
+                            self.attributes.state = AgentState::Working;
+                            continue;
+                        }
+
+                        AgentCommand::Issue.print_agent_message(
+                            &self.attributes.position,
+                            "Responses still do not match the declared schema, giving up on them",
                         );
+                    }
 
-                        // Create client request with timout 5s
-                        let client = Client::builder()
-                            .timeout(Duration::from_secs(5))
-                            .build()
-                            .unwrap();
-
-                        // Test endpoint
-                        let url = format!("localhost:8080/{}", endpoint.route);
-                        match check_status_code(&client, &url).await {
-                            Ok(status_code) => {
-                                if status_code != 200 {
+                    // The project carries the same checks as tests/api.rs, cargo test runs them
+                    match write_api_tests(
+                        WEB_SERVER_PROJECT_PATH,
+                        &api_endpoints,
+                        is_login_required,
+                    ) {
+                        Ok(()) => {
+                            tasklist.backend_files = read_project_files(WEB_SERVER_PROJECT_PATH);
+                            self.pending_tasks.push(String::from("write_api_tests"));
+                            let gate_outcome = self.run_quality_gate();
+                            self.record_revision();
+                            match gate_outcome {
+                                GateOutcome::Passed => AgentCommand::Test.print_agent_message(
+                                    &self.attributes.position,
+                                    format!("Generated {} passed", API_TESTS_PATH).as_str(),
+                                ),
+                                GateOutcome::Failed => {
+                                    self.attributes.state = AgentState::Working;
+                                    continue;
+                                }
+                                GateOutcome::Exhausted(stage) => {
                                     AgentCommand::Issue.print_agent_message(
                                         &self.attributes.position,
                                         format!(
-                                            "WARNING: Failed to call web server with endpoint {}",
-                                            endpoint.route
+                                            "{} still fails with the generated {}, giving up on it",
+                                            stage.get_name(),
+                                            API_TESTS_PATH
                                         )
                                         .as_str(),
                                     );
+                                    self.print_quality_report();
                                 }
                             }
-                            Err(e) => {
-                                // Kill if got error
-                                run_backend_server
-                                    .kill()
-                                    .expect("Failed to kill web server testing.");
-                                let err_msg: String = format!("Error checking: {}", e);
-                                AgentCommand::Issue
-                                    .print_agent_message(&self.attributes.position, &err_msg);
-                            }
+                        }
+                        Err(e) => AgentCommand::Issue.print_agent_message(
+                            &self.attributes.position,
+                            format!("Failed to write {}: {}", API_TESTS_PATH, e).as_str(),
+                        ),
+                    }
+
+                    // Which requirements have a route and a passing test behind them
+                    if let Some(requirements) = &tasklist.requirements {
+                        let report = build_traceability(
+                            requirements,
+                            &api_endpoints,
+                            &self.endpoint_results,
+                        );
+                        print_traceability_report(&self.attributes.position, &report);
+                        if let Err(e) = save_traceability_report(&run_dir, &report) {
+                            AgentCommand::Issue.print_agent_message(
+                                &self.attributes.position,
+                                format!("Failed to save traceability report: {}", e).as_str(),
+                            );
                         }
                     }
-                    save_api_endpoint(&gpt_response);
 
                     AgentCommand::Test.print_agent_message(
                         &self.attributes.position,
                         "Backend testing complete...",
                     );
 
-                    run_backend_server
-                        .kill()
-                        .expect("Failed to kill web server testing!");
-
                     self.attributes.state = AgentState::Done;
                 }
                 _ => self.attributes.state = AgentState::Done,
@@ -319,6 +895,8 @@ impl GeneralAgent for AgentBackend {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use crate::models::agent::pro::pro_trait::ProjectScope;
 
     use super::*;
@@ -337,6 +915,7 @@ mod tests {
                 is_crud_required: true,
                 is_user_login_and_logout: true,
                 is_external_urls_required: true,
+                ..ProjectScope::default()
             }),
             external_urls: Some(vec![
                 String::from("https://api.exchangerate-api.com/v4/latest/USD"),
@@ -344,13 +923,17 @@ mod tests {
                     "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd",
                 ),
             ]),
-            backend_code: None,
+            external_api_samples: None,
+            url_verdicts: None,
+            spec: None,
+            requirements: None,
+            backend_files: BTreeMap::new(),
             api_endpoint_schema: None,
         };
 
         let mut agent_backend = AgentBackend::new();
         agent_backend.initial_backend_code(&mut tasklist).await;
-        println!("{:#?}", tasklist.backend_code);
+        println!("{:#?}", tasklist.backend_files);
     }
 
     #[tokio::test]
@@ -361,6 +944,7 @@ mod tests {
                 is_crud_required: true,
                 is_user_login_and_logout: true,
                 is_external_urls_required: true,
+                ..ProjectScope::default()
             }),
             external_urls: Some(vec![
                 String::from("https://api.exchangerate-api.com/v4/latest/USD"),
@@ -368,7 +952,11 @@ mod tests {
                     "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd",
                 ),
             ]),
-            backend_code: Some("```rust\nuse actix_cors::Cors;\nuse actix_web::web::Json;\nuse actix_web::{http::header, web, App, HttpResponse, HttpServer, Responder};\nuse async_trait::async_trait;\nuse reqwest::Client as HttpClient;\nuse serde::{Deserialize, Serialize};\n\nuse std::collections::HashMap;\nuse std::fs;\nuse std::io::Write;\nuse std::sync::{Mutex, MutexGuard};\n\n#[derive(Serialize, Deserialize, Debug, Clone)]\nstruct Task {\n    id: u64,\n    name: String,\n    complete: bool,\n}\n\n#[derive(Serialize, Deserialize, Debug, Clone)]\nstruct User {\n    id: u64,\n    username: String,\n    password: String,\n}\n\n#[derive(Serialize, Deserialize, Debug, Clone)]\nstruct Database {\n    tasks: HashMap<u64, Task>,\n    users: HashMap<u64, User>,\n}\n\nimpl Database {\n    fn new() -> Self {\n        Self {\n            tasks: HashMap::new(),\n            users: HashMap::new(),\n        }\n    }\n\n    // TODO CRUD DATA\n    fn insert(&mut self, task: Task) {\n        self.tasks.insert(task.id, task);\n    }\n\n    fn get(&self, id: &u64) -> Option<&Task> {\n        self.tasks.get(id)\n    }\n\n    fn get_all(&self) -> Vec<&Task> {\n        self.tasks.values().collect()\n    }\n\n    fn update(&mut self, task: Task) {\n        self.tasks.insert(task.id, task);\n    }\n\n    fn delete(&mut self, id: &u64) {\n        self.tasks.remove(id);\n    }\n\n    // USER DATA RELATED FUNCTIONS\n    fn insert_user(&mut self, user: User) {\n        self.users.insert(user.id, user);\n    }\n\n    fn get_user_by_name(&self, username: &str) -> Option<&User> {\n        // return user\n        self.users.values().find(|u| u.username == username)\n    }\n\n    // DATABASE SAVING\n    fn save_to_file(&self) -> std::io::Result<()> {\n        // serde_json?\n        let data = serde_json::to_string(&self)?;\n        let mut file = fs::File::create(\"database.json\")?;\n        file.write_all(data.as_bytes())?;\n        Ok(())\n    }\n\n    fn load_file() -> std::io::Result<Self> {\n        let file_content = fs::read_to_string(\"database.json\")?;\n        let db: Self = serde_json::from_str(&file_content)?;\n        Ok(db)\n    }\n}\n\nstruct AppState {\n    db: Mutex<Database>,\n}\n\n// why return impl Responder instead of Responder?\nasync fn create_task(app_state: web::Data<AppState>, task: web::Json<Task>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.insert(task.into_inner()); // into_inner?\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn read_task(app_state: web::Data<AppState>, id: web::Path<u64>) -> impl Responder {\n    let db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    match db.get(&id.into_inner()) {\n        Some(task) => HttpResponse::Ok().json(task),\n        None => HttpResponse::NotFound().finish(),\n    }\n}\n\nasync fn read_all_task(app_state: web::Data<AppState>) -> impl Responder {\n    let db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    let tasks = db.get_all();\n    HttpResponse::Ok().json(tasks)\n}\n\nasync fn update_task(app_state: web::Data<AppState>, task: web::Json<Task>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.update(task.into_inner());\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn delete_task(app_state: web::Data<AppState>, id: web::Path<u64>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.delete(&id.into_inner());\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn register(app_state: web::Data<AppState>, user: web::Json<User>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.insert_user(user.into_inner());\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn login(app_state: web::Data<AppState>, user: web::Json<User>) -> impl Responder {\n    let db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    match db.get_user_by_name(&user.username) {\n        Some(stored_user) if stored_user.password == user.password => {\n            HttpResponse::Ok().body(\"Logged in!\")\n        }\n        _ => HttpResponse::BadRequest().body(\"Invalid username or password\"),\n    }\n}\n\n#[actix_web::main]\n// what is Result<()>\nasync fn main() -> std::io::Result<()> {\n    let db = match Database::load_file() {\n        Ok(db) => db,\n        Err(_) => Database::new(),\n    };\n\n    let data = web::Data::new(AppState { db: Mutex::new(db) });\n\n    // what is `move` used for in here?\n    HttpServer::new(move || {\n        App::new()\n            .wrap(\n                Cors::permissive()\n                    .allowed_origin_fn(|origin, _req_head| {\n                        origin.as_bytes().starts_with(b\"http://localhost\") || origin == \"null\"\n                    })\n                    .allowed_methods(vec![\"GET\", \"POST\", \"PUT\", \"DELETE\"])\n                    .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])\n                    .allowed_header(header::CONTENT_TYPE)\n                    .supports_credentials()\n                    .max_age(3600),\n            )\n            .app_data(data.clone())\n            .route(\"/task\", web::post().to(create_task))\n            .route(\"/task\", web::get().to(read_all_task))\n            .route(\"/task/{id}\", web::get().to(read_task))\n            .route(\"/task\", web::put().to(update_task))\n            .route(\"/task/{id}\", web::delete().to(delete_task))\n            .route(\"/register\", web::post().to(register))\n            .route(\"/login\", web::post().to(login))\n    })\n    .bind(\"127.0.0.1:8080\")?\n    .run()\n    .await\n}\n```".to_string()),
+            external_api_samples: None,
+            url_verdicts: None,
+            spec: None,
+            requirements: None,
+            backend_files: BTreeMap::from([(String::from("src/main.rs"), "```rust\nuse actix_cors::Cors;\nuse actix_web::web::Json;\nuse actix_web::{http::header, web, App, HttpResponse, HttpServer, Responder};\nuse async_trait::async_trait;\nuse reqwest::Client as HttpClient;\nuse serde::{Deserialize, Serialize};\n\nuse std::collections::HashMap;\nuse std::fs;\nuse std::io::Write;\nuse std::sync::{Mutex, MutexGuard};\n\n#[derive(Serialize, Deserialize, Debug, Clone)]\nstruct Task {\n    id: u64,\n    name: String,\n    complete: bool,\n}\n\n#[derive(Serialize, Deserialize, Debug, Clone)]\nstruct User {\n    id: u64,\n    username: String,\n    password: String,\n}\n\n#[derive(Serialize, Deserialize, Debug, Clone)]\nstruct Database {\n    tasks: HashMap<u64, Task>,\n    users: HashMap<u64, User>,\n}\n\nimpl Database {\n    fn new() -> Self {\n        Self {\n            tasks: HashMap::new(),\n            users: HashMap::new(),\n        }\n    }\n\n    // TODO CRUD DATA\n    fn insert(&mut self, task: Task) {\n        self.tasks.insert(task.id, task);\n    }\n\n    fn get(&self, id: &u64) -> Option<&Task> {\n        self.tasks.get(id)\n    }\n\n    fn get_all(&self) -> Vec<&Task> {\n        self.tasks.values().collect()\n    }\n\n    fn update(&mut self, task: Task) {\n        self.tasks.insert(task.id, task);\n    }\n\n    fn delete(&mut self, id: &u64) {\n        self.tasks.remove(id);\n    }\n\n    // USER DATA RELATED FUNCTIONS\n    fn insert_user(&mut self, user: User) {\n        self.users.insert(user.id, user);\n    }\n\n    fn get_user_by_name(&self, username: &str) -> Option<&User> {\n        // return user\n        self.users.values().find(|u| u.username == username)\n    }\n\n    // DATABASE SAVING\n    fn save_to_file(&self) -> std::io::Result<()> {\n        // serde_json?\n        let data = serde_json::to_string(&self)?;\n        let mut file = fs::File::create(\"database.json\")?;\n        file.write_all(data.as_bytes())?;\n        Ok(())\n    }\n\n    fn load_file() -> std::io::Result<Self> {\n        let file_content = fs::read_to_string(\"database.json\")?;\n        let db: Self = serde_json::from_str(&file_content)?;\n        Ok(db)\n    }\n}\n\nstruct AppState {\n    db: Mutex<Database>,\n}\n\n// why return impl Responder instead of Responder?\nasync fn create_task(app_state: web::Data<AppState>, task: web::Json<Task>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.insert(task.into_inner()); // into_inner?\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn read_task(app_state: web::Data<AppState>, id: web::Path<u64>) -> impl Responder {\n    let db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    match db.get(&id.into_inner()) {\n        Some(task) => HttpResponse::Ok().json(task),\n        None => HttpResponse::NotFound().finish(),\n    }\n}\n\nasync fn read_all_task(app_state: web::Data<AppState>) -> impl Responder {\n    let db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    let tasks = db.get_all();\n    HttpResponse::Ok().json(tasks)\n}\n\nasync fn update_task(app_state: web::Data<AppState>, task: web::Json<Task>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.update(task.into_inner());\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn delete_task(app_state: web::Data<AppState>, id: web::Path<u64>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.delete(&id.into_inner());\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn register(app_state: web::Data<AppState>, user: web::Json<User>) -> impl Responder {\n    let mut db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    db.insert_user(user.into_inner());\n    let _ = db.save_to_file();\n    HttpResponse::Ok().finish()\n}\n\nasync fn login(app_state: web::Data<AppState>, user: web::Json<User>) -> impl Responder {\n    let db: std::sync::MutexGuard<Database> = app_state.db.lock().unwrap();\n    match db.get_user_by_name(&user.username) {\n        Some(stored_user) if stored_user.password == user.password => {\n            HttpResponse::Ok().body(\"Logged in!\")\n        }\n        _ => HttpResponse::BadRequest().body(\"Invalid username or password\"),\n    }\n}\n\n#[actix_web::main]\n// what is Result<()>\nasync fn main() -> std::io::Result<()> {\n    let db = match Database::load_file() {\n        Ok(db) => db,\n        Err(_) => Database::new(),\n    };\n\n    let data = web::Data::new(AppState { db: Mutex::new(db) });\n\n    // what is `move` used for in here?\n    HttpServer::new(move || {\n        App::new()\n            .wrap(\n                Cors::permissive()\n                    .allowed_origin_fn(|origin, _req_head| {\n                        origin.as_bytes().starts_with(b\"http://localhost\") || origin == \"null\"\n                    })\n                    .allowed_methods(vec![\"GET\", \"POST\", \"PUT\", \"DELETE\"])\n                    .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT])\n                    .allowed_header(header::CONTENT_TYPE)\n                    .supports_credentials()\n                    .max_age(3600),\n            )\n            .app_data(data.clone())\n            .route(\"/task\", web::post().to(create_task))\n            .route(\"/task\", web::get().to(read_all_task))\n            .route(\"/task/{id}\", web::get().to(read_task))\n            .route(\"/task\", web::put().to(update_task))\n            .route(\"/task/{id}\", web::delete().to(delete_task))\n            .route(\"/register\", web::post().to(register))\n            .route(\"/login\", web::post().to(login))\n    })\n    .bind(\"127.0.0.1:8080\")?\n    .run()\n    .await\n}\n```".to_string())]),
             api_endpoint_schema: None,
         };
 
@@ -384,6 +972,7 @@ mod tests {
                 is_crud_required: true,
                 is_user_login_and_logout: true,
                 is_external_urls_required: true,
+                ..ProjectScope::default()
             }),
             external_urls: Some(vec![
                 String::from("https://api.exchangerate-api.com/v4/latest/USD"),
@@ -391,7 +980,11 @@ mod tests {
                     "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd",
                 ),
             ]),
-            backend_code: None,
+            external_api_samples: None,
+            url_verdicts: None,
+            spec: None,
+            requirements: None,
+            backend_files: BTreeMap::new(),
             api_endpoint_schema: None,
         };
 
@@ -0,0 +1,113 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::helper::openapi::to_json_schema;
+use crate::models::agent::pro::route_schema::BodySchema;
+
+// const
+const MAX_SAMPLE_ITEMS: usize = 3;
+const MAX_SAMPLE_STRING_CHARS: usize = 80;
+const MAX_SAMPLE_DEPTH: usize = 6;
+const MAX_SAMPLE_CHARS: usize = 2000;
+
+// A successful JSON response of an external url, kept so generated structs match real fields
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiSample {
+    pub url: String,
+    // Truncated JSON text of the response
+    pub sample: String,
+    // JSON Schema inferred from the whole response
+    pub schema: Value,
+}
+
+impl ApiSample {
+    pub fn from_response(url: &str, response: &Value) -> Self {
+        let mut sample = truncate_sample(response, 0).to_string();
+        if sample.chars().count() > MAX_SAMPLE_CHARS {
+            sample = sample.chars().take(MAX_SAMPLE_CHARS).collect::<String>() + "...";
+        }
+
+        Self {
+            url: url.to_string(),
+            sample,
+            schema: to_json_schema(&BodySchema::infer(response)),
+        }
+    }
+}
+
+// Keep the first items of arrays, the start of long strings and nothing too deep
+pub fn truncate_sample(value: &Value, depth: usize) -> Value {
+    match value {
+        Value::String(text) if text.chars().count() > MAX_SAMPLE_STRING_CHARS => Value::String(
+            text.chars()
+                .take(MAX_SAMPLE_STRING_CHARS)
+                .collect::<String>()
+                + "...",
+        ),
+        Value::Array(_) | Value::Object(_) if depth >= MAX_SAMPLE_DEPTH => {
+            Value::String(String::from("..."))
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .take(MAX_SAMPLE_ITEMS)
+                .map(|item| truncate_sample(item, depth + 1))
+                .collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), truncate_sample(value, depth + 1)))
+                .collect::<Map<String, Value>>(),
+        ),
+        value => value.clone(),
+    }
+}
+
+// Samples for a prompt, each one with its url, schema and example
+pub fn render_api_samples(samples: &[ApiSample]) -> String {
+    samples
+        .iter()
+        .map(|sample| {
+            format!(
+                "URL: {}\nJSON SCHEMA: {}\nSAMPLE RESPONSE: {}",
+                sample.url, sample.schema, sample.sample
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_api_sample_from_response() {
+        let response = json!({
+            "symbols": (0..50)
+                .map(|index| json!({ "symbol": format!("COIN{}", index), "price": index }))
+                .collect::<Vec<Value>>(),
+            "description": "x".repeat(200)
+        });
+
+        let sample = ApiSample::from_response("https://api.example.com/prices", &response);
+        let truncated: Value = serde_json::from_str(&sample.sample).unwrap();
+
+        assert_eq!(
+            truncated["symbols"].as_array().unwrap().len(),
+            MAX_SAMPLE_ITEMS
+        );
+        assert_eq!(
+            truncated["description"].as_str().unwrap().len(),
+            MAX_SAMPLE_STRING_CHARS + 3
+        );
+        assert_eq!(
+            sample.schema["properties"]["symbols"]["items"]["required"],
+            json!(["price", "symbol"])
+        );
+        assert!(render_api_samples(&[sample]).starts_with("URL: https://api.example.com/prices\n"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    env,
+    io::{stdin, IsTerminal},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// What a question is about, so known topics get a documented default answer
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ClarifyTopic {
+    Auth,
+    Persistence,
+    DataSource,
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+impl ClarifyTopic {
+    // Asked, in this order, when the questions cannot come from the analyst
+    pub const DEFAULTS: [ClarifyTopic; 3] = [
+        ClarifyTopic::Auth,
+        ClarifyTopic::Persistence,
+        ClarifyTopic::DataSource,
+    ];
+
+    // Documented defaults:
+    //   auth        -> no login, every route is public
+    //   persistence -> a JSON file next to the server
+    //   data_source -> only public APIs that need no key
+    pub fn default_answer(&self) -> Option<&'static str> {
+        match self {
+            ClarifyTopic::Auth => Some("No login, every route is public"),
+            ClarifyTopic::Persistence => Some("Store the data in a JSON file next to the server"),
+            ClarifyTopic::DataSource => {
+                Some("Only public APIs that need no key, or no external data at all")
+            }
+            ClarifyTopic::Other => None,
+        }
+    }
+
+    fn default_question(&self) -> &'static str {
+        match self {
+            ClarifyTopic::Auth => "Do users need to sign up and log in?",
+            ClarifyTopic::Persistence => "Where should the data be stored?",
+            ClarifyTopic::DataSource => "Which external data source should be used, if any?",
+            ClarifyTopic::Other => "Anything else the website must do?",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct ClarifyingQuestion {
+    // Unknown topics read as other, so any string is accepted
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub topic: ClarifyTopic,
+    pub question: String,
+    // Suggested by the analyst, only used for topics without a documented default
+    #[serde(default)]
+    pub default: String,
+}
+
+impl ClarifyingQuestion {
+    pub fn default_answer(&self) -> String {
+        match self.topic.default_answer() {
+            Some(answer) => answer.to_string(),
+            None if !self.default.trim().is_empty() => self.default.trim().to_string(),
+            None => String::from("No preference"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Clarification {
+    pub question: String,
+    pub answer: String,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClarifyConfig {
+    pub max_questions: usize,
+    pub is_interactive: bool,
+}
+
+impl ClarifyConfig {
+    // PLOTTO_CLARIFY_QUESTIONS=n turns the phase on, PLOTTO_NON_INTERACTIVE=1 or a piped stdin uses the defaults
+    pub fn from_env() -> Self {
+        let max_questions = env::var("PLOTTO_CLARIFY_QUESTIONS")
+            .ok()
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(0);
+        let is_non_interactive = env::var("PLOTTO_NON_INTERACTIVE")
+            .map(|flag| flag == "1" || flag.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        Self {
+            max_questions,
+            is_interactive: !is_non_interactive && stdin().is_terminal(),
+        }
+    }
+}
+
+// Drop blank analyst questions and keep at most max_questions
+pub fn keep_questions(
+    questions: Vec<ClarifyingQuestion>,
+    max_questions: usize,
+) -> Vec<ClarifyingQuestion> {
+    questions
+        .into_iter()
+        .filter(|question| !question.question.trim().is_empty())
+        .take(max_questions)
+        .collect()
+}
+
+pub fn default_questions(max_questions: usize) -> Vec<ClarifyingQuestion> {
+    ClarifyTopic::DEFAULTS
+        .iter()
+        .take(max_questions)
+        .map(|topic| ClarifyingQuestion {
+            topic: *topic,
+            question: topic.default_question().to_string(),
+            default: String::new(),
+        })
+        .collect()
+}
+
+// An empty answer from ask means the default answer
+pub fn answer_questions(
+    questions: &[ClarifyingQuestion],
+    mut ask: impl FnMut(&ClarifyingQuestion) -> String,
+) -> Vec<Clarification> {
+    questions
+        .iter()
+        .map(|question| {
+            let answer = ask(question).trim().to_string();
+            Clarification {
+                question: question.question.clone(),
+                is_default: answer.is_empty(),
+                answer: if answer.is_empty() {
+                    question.default_answer()
+                } else {
+                    answer
+                },
+            }
+        })
+        .collect()
+}
+
+pub fn fold_into_goal(goal: &str, clarifications: &[Clarification]) -> String {
+    if clarifications.is_empty() {
+        return goal.to_string();
+    }
+
+    let answers = clarifications
+        .iter()
+        .map(|clarification| {
+            format!(
+                "- {} {}",
+                clarification.question,
+                clarification.answer.trim_end_matches('.')
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!("{}\nCLARIFICATIONS:\n{}", goal, answers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::structured::{parse_structured, schema_of};
+
+    #[test]
+    fn test_keep_questions() {
+        let schema = schema_of::<Vec<ClarifyingQuestion>>();
+        let response = r#"Here are my questions:
+        ```
+        [
+          { "topic": "auth", "question": "Do users log in?", "default": "yes" },
+          { "topic": "payments", "question": "Which payment provider?", "default": "Stripe" },
+          { "topic": "other", "question": " " },
+          { "topic": "persistence", "question": "SQLite or a JSON file?" }
+        ]
+        ```"#;
+
+        let questions = keep_questions(parse_structured(response, &schema).unwrap(), 2);
+
+        assert_eq!(questions.len(), 2);
+        assert_eq!(questions[1].topic, ClarifyTopic::Other);
+        assert_eq!(
+            questions[0].default_answer(),
+            "No login, every route is public"
+        );
+        assert_eq!(questions[1].default_answer(), "Stripe");
+        assert!(parse_structured::<Vec<ClarifyingQuestion>>("not json", &schema).is_err());
+    }
+
+    #[test]
+    fn test_answer_questions_and_fold_into_goal() {
+        let questions = default_questions(5);
+        assert_eq!(questions.len(), ClarifyTopic::DEFAULTS.len());
+
+        let mut answers = vec!["", "SQLite please", ""].into_iter();
+        let clarifications = answer_questions(&questions, |_| answers.next().unwrap().to_string());
+
+        assert!(clarifications[0].is_default);
+        assert!(!clarifications[1].is_default);
+        assert_eq!(clarifications[1].answer, "SQLite please");
+
+        let goal = fold_into_goal("build a website that tracks books", &clarifications[..2]);
+        assert_eq!(
+            goal,
+            "build a website that tracks books\nCLARIFICATIONS:\n\
+             - Do users need to sign up and log in? No login, every route is public\n\
+             - Where should the data be stored? SQLite please"
+        );
+        assert_eq!(fold_into_goal("build a website", &[]), "build a website");
+    }
+}
@@ -1,3 +1,9 @@
 pub mod agent_analyst;
 pub mod agent_backend;
+pub mod api_sample;
+pub mod clarification;
 pub mod pro_trait;
+pub mod project_scope;
+pub mod project_spec;
+pub mod route_schema;
+pub mod traceability;
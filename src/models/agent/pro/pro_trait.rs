@@ -1,22 +1,68 @@
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
+use crate::helper::url_validation::UrlVerdict;
 use crate::models::agent::basic::basic_agent::BasicAgent;
+use crate::models::agent::pro::api_sample::ApiSample;
+use crate::models::agent::pro::clarification::Clarification;
+use crate::models::agent::pro::project_scope::{
+    AuthType, EndpointSpec, Entity, Filter, Persistence, Relationship, ScheduledJob,
+};
+use crate::models::agent::pro::project_spec::ProjectSpec;
+use crate::models::agent::pro::route_schema::{
+    deserialize_flag, flag_schema, BodySchema, HttpMethod,
+};
+use crate::models::agent::pro::traceability::Requirement;
 
-#[derive(Debug, Deserialize, Clone)]
+// Older answers only have the three flags, everything else defaults to empty
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Default)]
 pub struct ProjectScope {
     pub is_crud_required: bool,
     pub is_user_login_and_logout: bool,
     pub is_external_urls_required: bool,
+    #[serde(default)]
+    pub entities: Vec<Entity>,
+    #[serde(default)]
+    pub relationships: Vec<Relationship>,
+    #[serde(default)]
+    pub endpoints: Vec<EndpointSpec>,
+    #[serde(default)]
+    pub auth_type: AuthType,
+    #[serde(default)]
+    pub persistence: Persistence,
+    // Entities whose list endpoints are paginated
+    #[serde(default)]
+    pub pagination: Vec<String>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    #[serde(default)]
+    pub scheduled_jobs: Vec<ScheduledJob>,
+    // Answers the scope was built from, asked before planning
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clarifications: Vec<Clarification>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
 pub struct RouteObject {
-    pub is_route_dynamic: String,
-    pub method: String,
-    pub request_body: serde_json::Value,
-    pub response: serde_json::Value,
+    #[serde(deserialize_with = "deserialize_flag")]
+    #[schemars(schema_with = "flag_schema")]
+    pub is_route_dynamic: bool,
+    pub method: HttpMethod,
+    pub request_body: BodySchema,
+    pub response: BodySchema,
     pub route: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handler: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_body_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_params_type: Option<String>,
+    // Requirement ids from the "/// Requirements:" doc comment of the handler
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requirement_ids: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -24,7 +70,12 @@ pub struct TaskList {
     pub description: String,
     pub project_scope: Option<ProjectScope>,
     pub external_urls: Option<Vec<String>>,
-    pub backend_code: Option<String>,
+    pub external_api_samples: Option<Vec<ApiSample>>,
+    pub url_verdicts: Option<Vec<UrlVerdict>>,
+    // Requirements file given with plotto new --spec
+    pub spec: Option<ProjectSpec>,
+    pub requirements: Option<Vec<Requirement>>,
+    pub backend_files: BTreeMap<String, String>,
     pub api_endpoint_schema: Option<Vec<RouteObject>>,
 }
 
@@ -0,0 +1,288 @@
+use std::{collections::HashSet, fmt};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::models::agent::pro::pro_trait::ProjectScope;
+use crate::models::agent::pro::route_schema::{HttpMethod, PrimitiveType};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct EntityField {
+    pub name: String,
+    // Any spelling PrimitiveType::from_keyword understands: "string", "u64", "bool"...
+    #[serde(rename = "type")]
+    pub field_type: String,
+    #[serde(default)]
+    pub is_optional: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Entity {
+    pub name: String,
+    pub fields: Vec<EntityField>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipKind {
+    OneToOne,
+    OneToMany,
+    ManyToMany,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Relationship {
+    pub from: String,
+    pub to: String,
+    pub kind: RelationshipKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct EndpointSpec {
+    pub method: HttpMethod,
+    pub path: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthType {
+    #[default]
+    None,
+    Session,
+    Jwt,
+    ApiKey,
+    Basic,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Persistence {
+    #[default]
+    JsonFile,
+    Sqlite,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct Filter {
+    pub entity: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct ScheduledJob {
+    pub name: String,
+    // Cron expression or plain words, e.g. "every 5 minutes"
+    pub schedule: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ScopeError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ScopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid project scope: {}", self.problems.join("; "))
+    }
+}
+
+impl std::error::Error for ScopeError {}
+
+impl ProjectScope {
+    // Every name that is referenced must be declared, and the flags must agree with the details
+    pub fn validate(&self) -> Result<(), ScopeError> {
+        let mut problems: Vec<String> = vec![];
+
+        if !self.is_crud_required
+            && !self.is_user_login_and_logout
+            && !self.is_external_urls_required
+        {
+            problems.push(String::from("at least one of the bool flags must be true"));
+        }
+
+        let mut entity_names: HashSet<&str> = HashSet::new();
+        for entity in &self.entities {
+            if entity.name.trim().is_empty() {
+                problems.push(String::from("entity with an empty name"));
+            } else if !entity_names.insert(entity.name.as_str()) {
+                problems.push(format!("entity {} is declared twice", entity.name));
+            }
+
+            let mut field_names: HashSet<&str> = HashSet::new();
+            for field in &entity.fields {
+                if field.name.trim().is_empty() {
+                    problems.push(format!(
+                        "entity {} has a field with an empty name",
+                        entity.name
+                    ));
+                } else if !field_names.insert(field.name.as_str()) {
+                    problems.push(format!(
+                        "field {}.{} is declared twice",
+                        entity.name, field.name
+                    ));
+                }
+                if PrimitiveType::from_keyword(&field.field_type) == PrimitiveType::Any
+                    && !field.field_type.eq_ignore_ascii_case("any")
+                    && !entity_names_contain(&self.entities, &field.field_type)
+                {
+                    problems.push(format!(
+                        "field {}.{} has unknown type {}",
+                        entity.name, field.name, field.field_type
+                    ));
+                }
+            }
+        }
+        let mut check_entity = |name: &str, context: &str| {
+            if !entity_names.contains(name) {
+                problems.push(format!("{} refers to unknown entity {}", context, name));
+            }
+        };
+
+        for relationship in &self.relationships {
+            check_entity(&relationship.from, "relationship");
+            check_entity(&relationship.to, "relationship");
+        }
+        for entity in &self.pagination {
+            check_entity(entity, "pagination");
+        }
+        for filter in &self.filters {
+            check_entity(&filter.entity, "filter");
+        }
+
+        for filter in &self.filters {
+            let Some(entity) = self
+                .entities
+                .iter()
+                .find(|entity| entity.name == filter.entity)
+            else {
+                continue;
+            };
+            for field in &filter.fields {
+                if !entity
+                    .fields
+                    .iter()
+                    .any(|entity_field| entity_field.name == *field)
+                {
+                    problems.push(format!(
+                        "filter field {}.{} is not a field of the entity",
+                        filter.entity, field
+                    ));
+                }
+            }
+        }
+
+        let mut endpoint_keys: HashSet<(HttpMethod, &str)> = HashSet::new();
+        for endpoint in &self.endpoints {
+            if !endpoint.path.starts_with('/') {
+                problems.push(format!("endpoint path {} must start with /", endpoint.path));
+            }
+            if !endpoint_keys.insert((endpoint.method, endpoint.path.as_str())) {
+                problems.push(format!(
+                    "endpoint {} {} is listed twice",
+                    endpoint.method, endpoint.path
+                ));
+            }
+        }
+
+        if self.is_user_login_and_logout && self.auth_type == AuthType::None {
+            problems.push(String::from("login is required but auth_type is none"));
+        }
+
+        for job in &self.scheduled_jobs {
+            if job.name.trim().is_empty() || job.schedule.trim().is_empty() {
+                problems.push(String::from("scheduled jobs need a name and a schedule"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ScopeError { problems })
+        }
+    }
+}
+
+fn entity_names_contain(entities: &[Entity], type_name: &str) -> bool {
+    let type_name = type_name
+        .trim_start_matches("Vec<")
+        .trim_start_matches("Option<")
+        .trim_end_matches('>');
+    entities.iter().any(|entity| entity.name == type_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::structured::{parse_structured, schema_of};
+
+    fn parse_scope(response: &str) -> Result<ProjectScope, Vec<String>> {
+        parse_structured(response, &schema_of::<ProjectScope>())
+    }
+
+    #[test]
+    fn test_project_scope_legacy_flags() {
+        let scope = parse_scope(
+            r#"{ "is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false }"#,
+        )
+        .unwrap();
+
+        assert!(scope.entities.is_empty());
+        assert_eq!(scope.persistence, Persistence::JsonFile);
+        assert_eq!(scope.auth_type, AuthType::None);
+    }
+
+    #[test]
+    fn test_project_scope_validate() {
+        let response = r#"```json
+        {
+          "is_crud_required": true,
+          "is_user_login_and_logout": true,
+          "is_external_urls_required": false,
+          "entities": [
+            { "name": "User", "fields": [{ "name": "id", "type": "u64" }, { "name": "email", "type": "string" }] },
+            { "name": "Task", "fields": [
+              { "name": "id", "type": "u64" },
+              { "name": "owner", "type": "User" },
+              { "name": "done", "type": "bool" },
+              { "name": "note", "type": "string", "is_optional": true }
+            ] }
+          ],
+          "relationships": [{ "from": "User", "to": "Task", "kind": "one_to_many" }],
+          "endpoints": [{ "method": "GET", "path": "/tasks", "description": "List tasks" }],
+          "auth_type": "jwt",
+          "persistence": "sqlite",
+          "pagination": ["Task"],
+          "filters": [{ "entity": "Task", "fields": ["done"] }],
+          "scheduled_jobs": [{ "name": "purge_done", "schedule": "every day" }]
+        }
+        ```"#;
+
+        let scope = parse_scope(response).unwrap();
+        assert_eq!(scope.entities[1].fields.len(), 4);
+        assert_eq!(scope.relationships[0].kind, RelationshipKind::OneToMany);
+        assert_eq!(scope.endpoints[0].method, HttpMethod::Get);
+        assert_eq!(scope.persistence, Persistence::Sqlite);
+
+        let mut broken = scope.clone();
+        broken.auth_type = AuthType::None;
+        broken.relationships[0].to = String::from("Project");
+        broken.filters[0].fields.push(String::from("priority"));
+        broken.endpoints.push(broken.endpoints[0].clone());
+        broken.entities[0].fields[1].field_type = String::from("Money");
+
+        assert_eq!(
+            broken.validate().unwrap_err().problems,
+            vec![
+                "field User.email has unknown type Money",
+                "relationship refers to unknown entity Project",
+                "filter field Task.priority is not a field of the entity",
+                "endpoint get /tasks is listed twice",
+                "login is required but auth_type is none",
+            ]
+        );
+    }
+}
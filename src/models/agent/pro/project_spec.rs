@@ -0,0 +1,246 @@
+use std::{fmt, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::agent::pro::pro_trait::ProjectScope;
+use crate::models::agent::pro::project_scope::{
+    AuthType, EndpointSpec, Entity, Filter, Persistence, Relationship, ScheduledJob,
+};
+
+// Requirements written down by the user, as YAML or as Markdown with optional YAML front matter
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ProjectSpec {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub entities: Vec<Entity>,
+    #[serde(default)]
+    pub relationships: Vec<Relationship>,
+    #[serde(default, alias = "routes")]
+    pub endpoints: Vec<EndpointSpec>,
+    #[serde(default, alias = "external_urls")]
+    pub external_apis: Vec<String>,
+    // None when the spec leaves the choice to the analyst
+    #[serde(default)]
+    pub auth_type: Option<AuthType>,
+    #[serde(default)]
+    pub persistence: Option<Persistence>,
+    #[serde(default)]
+    pub pagination: Vec<String>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    #[serde(default)]
+    pub scheduled_jobs: Vec<ScheduledJob>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SpecError {
+    Read(String),
+    Parse(String),
+    Empty,
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Read(e) => write!(f, "failed to read spec: {}", e),
+            SpecError::Parse(e) => write!(f, "failed to parse spec: {}", e),
+            SpecError::Empty => write!(f, "spec has no description, entities or endpoints"),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+impl ProjectSpec {
+    // requirements.yaml / .yml is structured, anything else is read as Markdown
+    pub fn from_file(path: &Path) -> Result<Self, SpecError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| SpecError::Read(format!("{}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml(&text),
+            _ => Self::from_markdown(&text),
+        }
+    }
+
+    pub fn from_yaml(text: &str) -> Result<Self, SpecError> {
+        let spec: ProjectSpec =
+            serde_yaml::from_str(text).map_err(|e| SpecError::Parse(e.to_string()))?;
+        spec.check_not_empty()
+    }
+
+    // Front matter between two --- lines holds the structured fields, the body is the description
+    pub fn from_markdown(text: &str) -> Result<Self, SpecError> {
+        let (front_matter, body) = match text
+            .strip_prefix("---")
+            .and_then(|rest| rest.split_once("\n---"))
+        {
+            Some((yaml, body)) => (Some(yaml), body.trim_start_matches('-')),
+            None => (None, text),
+        };
+
+        let mut spec: ProjectSpec = match front_matter {
+            Some(yaml) => {
+                serde_yaml::from_str(yaml).map_err(|e| SpecError::Parse(e.to_string()))?
+            }
+            None => ProjectSpec::default(),
+        };
+        spec.description = [spec.description.trim(), body.trim()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n\n");
+
+        spec.check_not_empty()
+    }
+
+    fn check_not_empty(self) -> Result<Self, SpecError> {
+        if self.description.trim().is_empty()
+            && self.entities.is_empty()
+            && self.endpoints.is_empty()
+        {
+            return Err(SpecError::Empty);
+        }
+        Ok(self)
+    }
+
+    // What the spec already decides, shown to the analyst before it fills the rest
+    pub fn prefilled_scope(&self) -> ProjectScope {
+        ProjectScope {
+            is_crud_required: !self.entities.is_empty(),
+            is_user_login_and_logout: self
+                .auth_type
+                .is_some_and(|auth_type| auth_type != AuthType::None),
+            is_external_urls_required: !self.external_apis.is_empty(),
+            entities: self.entities.clone(),
+            relationships: self.relationships.clone(),
+            endpoints: self.endpoints.clone(),
+            auth_type: self.auth_type.unwrap_or_default(),
+            persistence: self.persistence.unwrap_or_default(),
+            pagination: self.pagination.clone(),
+            filters: self.filters.clone(),
+            scheduled_jobs: self.scheduled_jobs.clone(),
+            clarifications: vec![],
+        }
+    }
+
+    // Keep everything the spec sets and take only the rest from the generated scope
+    pub fn fill_gaps(&self, generated: ProjectScope) -> ProjectScope {
+        fn pick<T: Clone>(from_spec: &[T], generated: Vec<T>) -> Vec<T> {
+            if from_spec.is_empty() {
+                generated
+            } else {
+                from_spec.to_vec()
+            }
+        }
+
+        let prefilled = self.prefilled_scope();
+        let is_user_login_and_logout = match self.auth_type {
+            Some(auth_type) => auth_type != AuthType::None,
+            None => generated.is_user_login_and_logout,
+        };
+
+        ProjectScope {
+            is_crud_required: prefilled.is_crud_required || generated.is_crud_required,
+            is_user_login_and_logout,
+            is_external_urls_required: prefilled.is_external_urls_required
+                || generated.is_external_urls_required,
+            entities: pick(&self.entities, generated.entities),
+            relationships: pick(&self.relationships, generated.relationships),
+            endpoints: pick(&self.endpoints, generated.endpoints),
+            auth_type: self.auth_type.unwrap_or(generated.auth_type),
+            persistence: self.persistence.unwrap_or(generated.persistence),
+            pagination: pick(&self.pagination, generated.pagination),
+            filters: pick(&self.filters, generated.filters),
+            scheduled_jobs: pick(&self.scheduled_jobs, generated.scheduled_jobs),
+            clarifications: generated.clarifications,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::structured::{parse_structured, schema_of};
+    use crate::models::agent::pro::route_schema::HttpMethod;
+
+    const YAML_SPEC: &str = r#"
+description: A reading list where users track the books they finished
+entities:
+  - name: Book
+    fields:
+      - { name: id, type: u64 }
+      - { name: title, type: string }
+      - { name: finished, type: bool }
+routes:
+  - { method: GET, path: /books, description: List books }
+  - { method: POST, path: /books }
+external_apis:
+  - https://openlibrary.org/search.json?q=rust
+persistence: sqlite
+"#;
+
+    #[test]
+    fn test_project_spec_from_yaml() {
+        let spec = ProjectSpec::from_yaml(YAML_SPEC).unwrap();
+
+        assert_eq!(spec.entities[0].fields.len(), 3);
+        assert_eq!(spec.endpoints[1].method, HttpMethod::Post);
+        assert_eq!(spec.auth_type, None);
+        assert_eq!(spec.persistence, Some(Persistence::Sqlite));
+        assert_eq!(ProjectSpec::from_yaml("{}"), Err(SpecError::Empty));
+    }
+
+    #[test]
+    fn test_project_spec_from_markdown() {
+        let plain = ProjectSpec::from_markdown("# Books\n\n1. Users add books\n").unwrap();
+        assert_eq!(plain.description, "# Books\n\n1. Users add books");
+        assert!(plain.entities.is_empty());
+
+        let with_front_matter = ProjectSpec::from_markdown(
+            "---\nauth_type: jwt\nexternal_urls: [https://api.example.com/books]\n---\n# Books\n\n1. Users add books\n",
+        )
+        .unwrap();
+        assert_eq!(with_front_matter.auth_type, Some(AuthType::Jwt));
+        assert_eq!(with_front_matter.external_apis.len(), 1);
+        assert_eq!(
+            with_front_matter.description,
+            "# Books\n\n1. Users add books"
+        );
+    }
+
+    #[test]
+    fn test_project_spec_fill_gaps() {
+        let spec = ProjectSpec::from_yaml(YAML_SPEC).unwrap();
+        let generated = ProjectScope {
+            is_crud_required: true,
+            is_user_login_and_logout: true,
+            auth_type: AuthType::Session,
+            persistence: Persistence::JsonFile,
+            pagination: vec![String::from("Book")],
+            ..ProjectScope::default()
+        };
+
+        let scope = spec.fill_gaps(generated);
+
+        assert_eq!(scope.entities, spec.entities);
+        assert_eq!(scope.endpoints, spec.endpoints);
+        assert_eq!(scope.persistence, Persistence::Sqlite);
+        assert_eq!(scope.auth_type, AuthType::Session);
+        assert_eq!(scope.pagination, vec![String::from("Book")]);
+        assert!(scope.is_external_urls_required);
+        assert!(scope.validate().is_ok());
+
+        // Filters on an entity the spec replaced are caught after the merge
+        let response = r#"{ "is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false,
+            "entities": [{ "name": "Author", "fields": [{ "name": "name", "type": "string" }] }],
+            "filters": [{ "entity": "Author", "fields": ["name"] }] }"#;
+        let generated: ProjectScope =
+            parse_structured(response, &schema_of::<ProjectScope>()).unwrap();
+        assert_eq!(
+            spec.fill_gaps(generated).validate().unwrap_err().problems,
+            vec!["filter refers to unknown entity Author"]
+        );
+    }
+}
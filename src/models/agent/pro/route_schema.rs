@@ -0,0 +1,540 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt, str::FromStr};
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Map, Value};
+
+use crate::helper::{openapi::to_json_schema, schema_check::check_schema};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
+    Patch,
+    Head,
+    Options,
+}
+
+impl HttpMethod {
+    pub const ALL: [HttpMethod; 7] = [
+        HttpMethod::Get,
+        HttpMethod::Post,
+        HttpMethod::Put,
+        HttpMethod::Delete,
+        HttpMethod::Patch,
+        HttpMethod::Head,
+        HttpMethod::Options,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "get",
+            HttpMethod::Post => "post",
+            HttpMethod::Put => "put",
+            HttpMethod::Delete => "delete",
+            HttpMethod::Patch => "patch",
+            HttpMethod::Head => "head",
+            HttpMethod::Options => "options",
+        }
+    }
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let method = s.trim().to_lowercase();
+        HttpMethod::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == method)
+            .ok_or_else(|| format!("unknown HTTP method {:?}", s))
+    }
+}
+
+impl Serialize for HttpMethod {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// "GET", "get" and "Get" are all accepted
+impl<'de> Deserialize<'de> for HttpMethod {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let method = String::deserialize(deserializer)?;
+        method.parse().map_err(de::Error::custom)
+    }
+}
+
+impl JsonSchema for HttpMethod {
+    fn schema_name() -> Cow<'static, str> {
+        Cow::Borrowed("HttpMethod")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let methods: Vec<String> = HttpMethod::ALL
+            .iter()
+            .flat_map(|method| [method.as_str().to_string(), method.as_str().to_uppercase()])
+            .collect();
+        json_schema!({ "type": "string", "enum": methods })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimitiveType {
+    String,
+    Number,
+    Bool,
+    // Maps and tuples whose contents are not described
+    Object,
+    Array,
+    Any,
+}
+
+impl PrimitiveType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrimitiveType::String => "string",
+            PrimitiveType::Number => "number",
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::Object => "object",
+            PrimitiveType::Array => "array",
+            PrimitiveType::Any => "any",
+        }
+    }
+
+    // Models spell types many ways: "number", "u64", "integer", "String", "boolean"...
+    pub fn from_keyword(keyword: &str) -> Self {
+        let trimmed = keyword.trim().trim_start_matches('&');
+        let is_type_name =
+            trimmed.starts_with(|c: char| c.is_ascii_uppercase()) || trimmed.contains(['<', ':']);
+
+        match trimmed.to_lowercase().as_str() {
+            "string" | "str" | "text" | "char" | "uuid" | "date" | "datetime" => {
+                PrimitiveType::String
+            }
+            "number" | "integer" | "int" | "float" | "double" | "u8" | "u16" | "u32" | "u64"
+            | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "f32"
+            | "f64" => PrimitiveType::Number,
+            "bool" | "boolean" => PrimitiveType::Bool,
+            "object" | "map" | "hashmap" | "dict" => PrimitiveType::Object,
+            "array" | "list" | "vec" => PrimitiveType::Array,
+            "any" => PrimitiveType::Any,
+            // Unknown rust types say nothing, anything else is an example string value
+            _ if is_type_name => PrimitiveType::Any,
+            _ => PrimitiveType::String,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    pub schema: BodySchema,
+    pub is_optional: bool,
+}
+
+// Shape of a request or response body
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodySchema {
+    None,
+    NotProvided,
+    Primitive(PrimitiveType),
+    Object(BTreeMap<String, SchemaField>),
+    Array(Box<BodySchema>),
+}
+
+impl BodySchema {
+    // Accepts the legacy shape format ({"id": "number", "note?": "string"}) as well as
+    // plain example JSON ({"id": 1, "note": null})
+    pub fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Null => BodySchema::None,
+            Value::Bool(_) => BodySchema::Primitive(PrimitiveType::Bool),
+            Value::Number(_) => BodySchema::Primitive(PrimitiveType::Number),
+            Value::String(keyword) => match keyword.trim().to_lowercase().as_str() {
+                "" | "none" | "null" | "()" => BodySchema::None,
+                "not_provided" | "not provided" | "unknown" => BodySchema::NotProvided,
+                _ => BodySchema::Primitive(PrimitiveType::from_keyword(keyword)),
+            },
+            Value::Array(items) => BodySchema::Array(Box::new(
+                items
+                    .first()
+                    .map(BodySchema::from_value)
+                    .unwrap_or(BodySchema::Primitive(PrimitiveType::Any)),
+            )),
+            Value::Object(object) => BodySchema::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| {
+                        let (name, is_optional) = match key.strip_suffix('?') {
+                            Some(name) => (name, true),
+                            None => (key.as_str(), false),
+                        };
+
+                        // A null example says nothing but that the field may be missing
+                        let field = match BodySchema::from_value(value) {
+                            BodySchema::None => SchemaField {
+                                schema: BodySchema::Primitive(PrimitiveType::Any),
+                                is_optional: true,
+                            },
+                            schema => SchemaField {
+                                schema,
+                                is_optional,
+                            },
+                        };
+
+                        (name.to_string(), field)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    // Schema of a real response, where strings are values and never type keywords.
+    // Array items are merged, a field missing or null in some of them becomes optional
+    pub fn infer(sample: &Value) -> Self {
+        BodySchema::infer_raw(sample).settle_nulls()
+    }
+
+    // Null fields stay None until merged so a later sample can give them a type
+    fn infer_raw(sample: &Value) -> Self {
+        match sample {
+            Value::Null => BodySchema::None,
+            Value::Bool(_) => BodySchema::Primitive(PrimitiveType::Bool),
+            Value::Number(_) => BodySchema::Primitive(PrimitiveType::Number),
+            Value::String(_) => BodySchema::Primitive(PrimitiveType::String),
+            Value::Array(items) => BodySchema::Array(Box::new(
+                items
+                    .iter()
+                    .map(BodySchema::infer_raw)
+                    .reduce(BodySchema::merge)
+                    .unwrap_or(BodySchema::Primitive(PrimitiveType::Any)),
+            )),
+            Value::Object(object) => BodySchema::Object(
+                object
+                    .iter()
+                    .map(|(name, value)| {
+                        let schema = BodySchema::infer_raw(value);
+                        let is_optional = schema == BodySchema::None;
+                        (
+                            name.clone(),
+                            SchemaField {
+                                schema,
+                                is_optional,
+                            },
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (left, right) if left == right => left,
+            (BodySchema::None, schema) | (schema, BodySchema::None) => schema,
+            (BodySchema::Array(left), BodySchema::Array(right)) => {
+                BodySchema::Array(Box::new(left.merge(*right)))
+            }
+            (BodySchema::Object(mut left), BodySchema::Object(right)) => {
+                for (name, field) in left.iter_mut() {
+                    if !right.contains_key(name) {
+                        field.is_optional = true;
+                    }
+                }
+                for (name, field) in right {
+                    let merged = match left.remove(&name) {
+                        Some(existing) => SchemaField {
+                            schema: existing.schema.merge(field.schema),
+                            is_optional: existing.is_optional || field.is_optional,
+                        },
+                        None => SchemaField {
+                            schema: field.schema,
+                            is_optional: true,
+                        },
+                    };
+                    left.insert(name, merged);
+                }
+                BodySchema::Object(left)
+            }
+            _ => BodySchema::Primitive(PrimitiveType::Any),
+        }
+    }
+
+    // Fields that were only ever null say nothing about their type
+    fn settle_nulls(self) -> Self {
+        match self {
+            BodySchema::Array(item) => match item.settle_nulls() {
+                BodySchema::None => {
+                    BodySchema::Array(Box::new(BodySchema::Primitive(PrimitiveType::Any)))
+                }
+                item => BodySchema::Array(Box::new(item)),
+            },
+            BodySchema::Object(fields) => BodySchema::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, field)| {
+                        let schema = match field.schema.settle_nulls() {
+                            BodySchema::None => BodySchema::Primitive(PrimitiveType::Any),
+                            schema => schema,
+                        };
+                        (name, SchemaField { schema, ..field })
+                    })
+                    .collect(),
+            ),
+            schema => schema,
+        }
+    }
+
+    // Structural mismatches of a live JSON value, each one prefixed with its path.
+    // Checked against the JSON Schema of the shape, the same check the generated api tests run
+    pub fn validate(&self, value: &Value) -> Vec<String> {
+        let mut errors: Vec<String> = vec![];
+        if !matches!(self, BodySchema::None | BodySchema::NotProvided) {
+            check_schema(value, &to_json_schema(self), "$", &mut errors);
+        }
+        errors
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            BodySchema::None => json!("None"),
+            BodySchema::NotProvided => json!("not_provided"),
+            BodySchema::Primitive(primitive) => json!(primitive.as_str()),
+            BodySchema::Object(fields) => {
+                let mut object = Map::new();
+                for (name, field) in fields {
+                    let key = if field.is_optional {
+                        format!("{}?", name)
+                    } else {
+                        name.clone()
+                    };
+                    object.insert(key, field.schema.to_value());
+                }
+                Value::Object(object)
+            }
+            BodySchema::Array(item) => json!([item.to_value()]),
+        }
+    }
+}
+
+impl Serialize for BodySchema {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BodySchema {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Ok(BodySchema::from_value(&value))
+    }
+}
+
+// Bodies are example values in any shape, from_value makes sense of them
+impl JsonSchema for BodySchema {
+    fn schema_name() -> Cow<'static, str> {
+        Cow::Borrowed("BodySchema")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        Schema::from(true)
+    }
+}
+
+// is_route_dynamic used to be printed as "true" / "false"
+pub fn deserialize_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Bool(flag) => Ok(flag),
+        Value::String(flag) => match flag.trim().to_lowercase().as_str() {
+            "true" | "yes" => Ok(true),
+            "false" | "no" | "" => Ok(false),
+            _ => Err(de::Error::custom(format!("invalid bool {:?}", flag))),
+        },
+        other => Err(de::Error::custom(format!("invalid bool {}", other))),
+    }
+}
+
+// Schema of the fields read with deserialize_flag
+pub fn flag_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({ "type": ["boolean", "string"] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::structured::schema_of;
+    use crate::models::agent::pro::pro_trait::RouteObject;
+
+    #[test]
+    fn test_body_schema_from_legacy_format() {
+        let schema = BodySchema::from_value(&json!({
+            "id": "number",
+            "name": "String",
+            "completed": "bool",
+            "tags?": ["string"]
+        }));
+
+        let BodySchema::Object(fields) = &schema else {
+            panic!("expected an object schema");
+        };
+        assert_eq!(
+            fields["name"].schema,
+            BodySchema::Primitive(PrimitiveType::String)
+        );
+        assert_eq!(
+            fields["tags"],
+            SchemaField {
+                schema: BodySchema::Array(Box::new(BodySchema::Primitive(PrimitiveType::String))),
+                is_optional: true,
+            }
+        );
+        assert_eq!(
+            schema.to_value(),
+            json!({
+                "id": "number",
+                "name": "string",
+                "completed": "bool",
+                "tags?": ["string"]
+            })
+        );
+
+        assert_eq!(BodySchema::from_value(&json!("None")), BodySchema::None);
+        assert_eq!(
+            BodySchema::from_value(&json!("not_provided")),
+            BodySchema::NotProvided
+        );
+    }
+
+    #[test]
+    fn test_body_schema_from_example_json() {
+        let schema = BodySchema::from_value(&json!({"id": 1, "done": false, "note": null}));
+
+        assert_eq!(
+            schema.to_value(),
+            json!({"id": "number", "done": "bool", "note?": "any"})
+        );
+        assert_eq!(BodySchema::from_value(&Value::Null), BodySchema::None);
+        assert_eq!(
+            BodySchema::from_value(&json!(["Vec<Item>"])).to_value(),
+            json!(["any"])
+        );
+    }
+
+    #[test]
+    fn test_infer_from_sample() {
+        let sample = json!([
+            { "symbol": "BTC", "price": 64000.5, "note": null, "tags": [] },
+            { "symbol": "number", "price": 3100, "note": "eth", "volume": 12 }
+        ]);
+
+        let schema = BodySchema::infer(&sample);
+
+        assert_eq!(
+            schema.to_value(),
+            json!([{
+                "symbol": "string",
+                "price": "number",
+                "note?": "string",
+                "tags?": ["any"],
+                "volume?": "number"
+            }])
+        );
+        assert!(schema.validate(&sample).is_empty());
+    }
+
+    #[test]
+    fn test_validate_response() {
+        let schema = BodySchema::from_value(&json!([{
+            "id": "number",
+            "name": "string",
+            "done?": "bool",
+            "owner": {"username": "string"}
+        }]));
+
+        let valid = json!([
+            {"id": 1, "name": "pen", "owner": {"username": "phat"}},
+            {"id": 2, "name": "ink", "done": null, "owner": {"username": "phat"}}
+        ]);
+        assert!(schema.validate(&valid).is_empty());
+
+        let invalid = json!([
+            {"id": "1", "title": "pen", "done": "yes", "owner": {"username": 7}}
+        ]);
+        assert_eq!(
+            schema.validate(&invalid),
+            vec![
+                "$[0].done: expected bool or null, got string",
+                "$[0].id: expected number, got string",
+                "$[0].name: missing field",
+                "$[0].owner.username: expected string, got number",
+                "$[0].title: unexpected field",
+            ]
+        );
+        assert_eq!(
+            schema.validate(&json!({"id": 1})),
+            vec!["$: expected array, got object"]
+        );
+        assert!(BodySchema::NotProvided
+            .validate(&json!("anything"))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_route_object_from_legacy_schema() {
+        let legacy = json!([
+            {
+                "route": "/item/{id}",
+                "is_route_dynamic": "true",
+                "method": "GET",
+                "request_body": "None",
+                "response": {"id": "number", "name": "string"}
+            },
+            {
+                "route": "/item",
+                "is_route_dynamic": false,
+                "method": "post",
+                "request_body": {"id": 1, "name": "pen"},
+                "response": null
+            }
+        ]);
+        let mut errors: Vec<String> = vec![];
+        check_schema(&legacy, &schema_of::<Vec<RouteObject>>(), "$", &mut errors);
+        assert!(errors.is_empty());
+
+        let routes: Vec<RouteObject> = serde_json::from_value(legacy).unwrap();
+
+        assert!(routes[0].is_route_dynamic);
+        assert_eq!(routes[0].method, HttpMethod::Get);
+        assert_eq!(routes[0].request_body, BodySchema::None);
+        assert!(matches!(routes[0].response, BodySchema::Object(_)));
+        assert!(!routes[1].is_route_dynamic);
+        assert_eq!(
+            routes[1].request_body.to_value(),
+            json!({"id": "number", "name": "string"})
+        );
+        assert_eq!(routes[1].response, BodySchema::None);
+
+        let saved = serde_json::to_value(&routes[0]).unwrap();
+        assert_eq!(saved["is_route_dynamic"], json!(true));
+        assert_eq!(saved["method"], json!("get"));
+    }
+
+    #[test]
+    fn test_http_method() {
+        assert_eq!("GET".parse::<HttpMethod>(), Ok(HttpMethod::Get));
+        assert_eq!(
+            serde_json::from_value::<HttpMethod>(json!("Delete")).unwrap(),
+            HttpMethod::Delete
+        );
+        assert_eq!(serde_json::to_value(HttpMethod::Put).unwrap(), json!("put"));
+        assert!("fetch".parse::<HttpMethod>().is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::helper::{command_line::AgentCommand, endpoints::EndpointResult};
+use crate::models::agent::pro::pro_trait::RouteObject;
+use crate::models::agent::pro::route_schema::HttpMethod;
+
+// const
+const TRACEABILITY_FILE_NAME: &str = "traceability.json";
+
+// One numbered thing the user asked for, referred to as R1, R2...
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Requirement {
+    pub id: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceGap {
+    NoRoute,
+    NoPassingTest,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RequirementTrace {
+    pub id: String,
+    pub text: String,
+    // "GET /tasks" for every route whose handler names the requirement
+    pub routes: Vec<String>,
+    pub passing_tests: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap: Option<TraceGap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceabilityReport {
+    pub requirements: Vec<RequirementTrace>,
+    // Routes that name no known requirement
+    pub unlinked_routes: Vec<String>,
+}
+
+// R1, R2... in the order given
+pub fn number_requirements(texts: &[String]) -> Vec<Requirement> {
+    texts
+        .iter()
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .enumerate()
+        .map(|(index, text)| Requirement {
+            id: format!("R{}", index + 1),
+            text: text.to_string(),
+        })
+        .collect()
+}
+
+// "1. Users can register" and "2) ..." lines of a written spec
+pub fn numbered_requirements(text: &str) -> Vec<Requirement> {
+    let items: Vec<String> = text
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+            if rest.len() == line.len() {
+                return None;
+            }
+            rest.strip_prefix(['.', ')'])
+                .filter(|item| item.starts_with(char::is_whitespace))
+                .map(|item| item.trim().to_string())
+        })
+        .collect();
+
+    number_requirements(&items)
+}
+
+// Requirements for a prompt, one "R1: text" per line
+pub fn render_requirements(requirements: &[Requirement]) -> String {
+    requirements
+        .iter()
+        .map(|requirement| format!("{}: {}", requirement.id, requirement.text))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn route_label(method: HttpMethod, route: &str) -> String {
+    format!("{} {}", method.as_str().to_uppercase(), route)
+}
+
+// Follow every requirement to the routes that implement it and the endpoint tests that passed on them
+pub fn build_traceability(
+    requirements: &[Requirement],
+    routes: &[RouteObject],
+    results: &[EndpointResult],
+) -> TraceabilityReport {
+    let traces = requirements
+        .iter()
+        .map(|requirement| {
+            let linked: Vec<&RouteObject> = routes
+                .iter()
+                .filter(|route| route.requirement_ids.contains(&requirement.id))
+                .collect();
+
+            let mut passing_tests: Vec<String> = vec![];
+            for result in results.iter().filter(|result| result.passed) {
+                let label = route_label(result.method, &result.route);
+                let is_linked = linked
+                    .iter()
+                    .any(|route| route.method == result.method && route.route == result.route);
+                if is_linked && !passing_tests.contains(&label) {
+                    passing_tests.push(label);
+                }
+            }
+
+            let gap = if linked.is_empty() {
+                Some(TraceGap::NoRoute)
+            } else if passing_tests.is_empty() {
+                Some(TraceGap::NoPassingTest)
+            } else {
+                None
+            };
+
+            RequirementTrace {
+                id: requirement.id.clone(),
+                text: requirement.text.clone(),
+                routes: linked
+                    .iter()
+                    .map(|route| route_label(route.method, &route.route))
+                    .collect(),
+                passing_tests,
+                gap,
+            }
+        })
+        .collect();
+
+    let unlinked_routes = routes
+        .iter()
+        .filter(|route| {
+            !route
+                .requirement_ids
+                .iter()
+                .any(|id| requirements.iter().any(|requirement| requirement.id == *id))
+        })
+        .map(|route| route_label(route.method, &route.route))
+        .collect();
+
+    TraceabilityReport {
+        requirements: traces,
+        unlinked_routes,
+    }
+}
+
+pub fn print_traceability_report(agent_position: &str, report: &TraceabilityReport) {
+    for trace in &report.requirements {
+        match trace.gap {
+            None => AgentCommand::Test.print_agent_message(
+                agent_position,
+                format!(
+                    "{} covered by {} ({} passing)",
+                    trace.id,
+                    trace.routes.join(", "),
+                    trace.passing_tests.len()
+                )
+                .as_str(),
+            ),
+            Some(TraceGap::NoRoute) => AgentCommand::Issue.print_agent_message(
+                agent_position,
+                format!("{} has no implementing route: {}", trace.id, trace.text).as_str(),
+            ),
+            Some(TraceGap::NoPassingTest) => AgentCommand::Issue.print_agent_message(
+                agent_position,
+                format!(
+                    "{} has no passing test on {}: {}",
+                    trace.id,
+                    trace.routes.join(", "),
+                    trace.text
+                )
+                .as_str(),
+            ),
+        }
+    }
+
+    if !report.unlinked_routes.is_empty() {
+        AgentCommand::Info.print_agent_message(
+            agent_position,
+            format!(
+                "Routes without a requirement: {}",
+                report.unlinked_routes.join(", ")
+            )
+            .as_str(),
+        );
+    }
+}
+
+pub fn save_traceability_report(
+    run_dir: &Path,
+    report: &TraceabilityReport,
+) -> std::io::Result<PathBuf> {
+    let path = run_dir.join(TRACEABILITY_FILE_NAME);
+    let json = serde_json::to_string_pretty(report).expect("Failed to encode traceability report");
+    fs::write(&path, json)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agent::pro::route_schema::BodySchema;
+
+    fn route(method: HttpMethod, path: &str, requirement_ids: &[&str]) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: path.contains('{'),
+            method,
+            request_body: BodySchema::None,
+            response: BodySchema::None,
+            route: path.to_string(),
+            handler: None,
+            json_body_type: None,
+            path_params_type: None,
+            requirement_ids: requirement_ids.iter().map(|id| id.to_string()).collect(),
+        }
+    }
+
+    fn result(method: HttpMethod, path: &str, passed: bool) -> EndpointResult {
+        EndpointResult {
+            method,
+            route: path.to_string(),
+            url: format!("http://127.0.0.1:8080{}", path),
+            status: Some(if passed { 200 } else { 500 }),
+            passed,
+            reason: None,
+            request_body: None,
+            response_body: None,
+            schema_errors: vec![],
+        }
+    }
+
+    #[test]
+    fn test_numbered_requirements() {
+        let spec = "# Books\n\n1. Users add books\n2) Users mark a book finished\n\n2024 was a good year\n10. Books are paginated";
+        let requirements = numbered_requirements(spec);
+
+        assert_eq!(
+            render_requirements(&requirements),
+            "R1: Users add books\nR2: Users mark a book finished\nR3: Books are paginated"
+        );
+        assert_eq!(
+            number_requirements(&[String::from("Users add books"), String::from(" ")]),
+            vec![Requirement {
+                id: String::from("R1"),
+                text: String::from("Users add books"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_traceability() {
+        let requirements = number_requirements(&[
+            String::from("Users add books"),
+            String::from("Users delete books"),
+            String::from("Users export books as CSV"),
+        ]);
+        let routes = vec![
+            route(HttpMethod::Post, "/books", &["R1"]),
+            route(HttpMethod::Get, "/books", &["R1"]),
+            route(HttpMethod::Delete, "/books/{id}", &["R2"]),
+            route(HttpMethod::Get, "/health", &[]),
+        ];
+        let results = vec![
+            result(HttpMethod::Post, "/books", true),
+            result(HttpMethod::Get, "/books", true),
+            result(HttpMethod::Delete, "/books/{id}", false),
+        ];
+
+        let report = build_traceability(&requirements, &routes, &results);
+        let gaps: Vec<Option<TraceGap>> =
+            report.requirements.iter().map(|trace| trace.gap).collect();
+
+        assert_eq!(
+            gaps,
+            vec![None, Some(TraceGap::NoPassingTest), Some(TraceGap::NoRoute)]
+        );
+        assert_eq!(
+            report.requirements[0].passing_tests,
+            vec!["POST /books", "GET /books"]
+        );
+        assert_eq!(report.unlinked_routes, vec!["GET /health"]);
+    }
+}
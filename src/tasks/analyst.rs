@@ -1,4 +1,6 @@
-use lib::function_to_string;
+use lib::{ai_function, function_to_string};
+
+use crate::models::agent::pro::{clarification::ClarifyingQuestion, pro_trait::ProjectScope};
 
 #[function_to_string]
 pub fn convert_user_input_to_goal(_user_request: &str) {
@@ -14,37 +16,114 @@ pub fn convert_user_input_to_goal(_user_request: &str) {
     println!(OUTPUT)
 }
 
-#[function_to_string]
+#[ai_function(output = ProjectScope, checked)]
 pub fn print_project_scope(_project_description: &str) {
     /// Input: Takes in a user request to build a website project description
-    /// Function: Converts user request into JSON response of information items required for a website build.
+    /// Function: Converts user request into JSON response of information items required for a website build, including its data model.
     /// Important: At least one of the bool results must be true
-    /// Output: Prints an object response in the following format:
+    /// Important: Lines under CLARIFICATIONS are answers from the user and win over any guess, e.g. a login answer decides auth_type and a storage answer decides persistence
+    /// Important: Every entity named in relationships, pagination and filters is declared in entities, and every filter field is a field of its entity
+    /// Important: Field types are "string", "number", "bool" or the name of another entity. Relationship kind is "one_to_one", "one_to_many" or "many_to_many"
+    /// Important: auth_type is "none", "session", "jwt", "api_key" or "basic", and never "none" when is_user_login_and_logout is true
+    /// Important: persistence is "json_file" for simple sites and "sqlite" when the data is relational or large
+    /// Output: Prints ONLY an object response in the following format:
     ///   {
     ///     "is_crud_required": bool, // true if site needs CRUD functionality
-    ///     "is_user_login_and_logout": bool // true if site needs users to be able to log in and log out
-    ///     "is_external_urls_required": bool // true if site needs to fetch data from third part providers
+    ///     "is_user_login_and_logout": bool, // true if site needs users to be able to log in and log out
+    ///     "is_external_urls_required": bool, // true if site needs to fetch data from third part providers
+    ///     "entities": [{ "name": string, "fields": [{ "name": string, "type": string, "is_optional": bool }] }],
+    ///     "relationships": [{ "from": entity name, "to": entity name, "kind": string }],
+    ///     "endpoints": [{ "method": string, "path": string, "description": string }],
+    ///     "auth_type": string,
+    ///     "persistence": string,
+    ///     "pagination": [entity name], // entities whose list endpoints are paginated
+    ///     "filters": [{ "entity": entity name, "fields": [field name] }],
+    ///     "scheduled_jobs": [{ "name": string, "schedule": string, "description": string }]
     ///   }
     /// Example 1:
     ///   user_request = "I need a full stack website that accepts users and gets stock price data"
     ///   prints:
     ///   {
-    ///     "is_crud_required": true
-    ///     "is_user_login_and_logout": true
-    ///     "is_external_urls_required": bool true
+    ///     "is_crud_required": true,
+    ///     "is_user_login_and_logout": true,
+    ///     "is_external_urls_required": true,
+    ///     "entities": [
+    ///       { "name": "User", "fields": [{ "name": "id", "type": "number" }, { "name": "username", "type": "string" }, { "name": "password", "type": "string" }] },
+    ///       { "name": "Watchlist", "fields": [{ "name": "id", "type": "number" }, { "name": "user_id", "type": "number" }, { "name": "symbol", "type": "string" }] }
+    ///     ],
+    ///     "relationships": [{ "from": "User", "to": "Watchlist", "kind": "one_to_many" }],
+    ///     "endpoints": [
+    ///       { "method": "POST", "path": "/register", "description": "Create a user" },
+    ///       { "method": "POST", "path": "/login", "description": "Log a user in" },
+    ///       { "method": "GET", "path": "/watchlist", "description": "List the symbols of the user" },
+    ///       { "method": "GET", "path": "/prices/{symbol}", "description": "Latest price of a symbol" }
+    ///     ],
+    ///     "auth_type": "jwt",
+    ///     "persistence": "sqlite",
+    ///     "pagination": [],
+    ///     "filters": [],
+    ///     "scheduled_jobs": [{ "name": "refresh_prices", "schedule": "every 5 minutes", "description": "Fetch the latest prices" }]
     ///   }
     /// Example 2:
     ///   user_request = "I need a simple TODO app"
     ///   prints:
     ///   {
-    ///     "is_crud_required": true
-    ///     "is_user_login_and_logout": false
-    ///     "is_external_urls_required": bool false
+    ///     "is_crud_required": true,
+    ///     "is_user_login_and_logout": false,
+    ///     "is_external_urls_required": false,
+    ///     "entities": [{ "name": "Task", "fields": [{ "name": "id", "type": "number" }, { "name": "title", "type": "string" }, { "name": "done", "type": "bool" }] }],
+    ///     "relationships": [],
+    ///     "endpoints": [
+    ///       { "method": "POST", "path": "/tasks", "description": "Create a task" },
+    ///       { "method": "GET", "path": "/tasks", "description": "List tasks" },
+    ///       { "method": "PUT", "path": "/tasks/{id}", "description": "Update a task" },
+    ///       { "method": "DELETE", "path": "/tasks/{id}", "description": "Delete a task" }
+    ///     ],
+    ///     "auth_type": "none",
+    ///     "persistence": "json_file",
+    ///     "pagination": ["Task"],
+    ///     "filters": [{ "entity": "Task", "fields": ["done"] }],
+    ///     "scheduled_jobs": []
     ///   }
     println!(OUTPUT)
 }
 
-#[function_to_string]
+#[ai_function(output = Vec<ClarifyingQuestion>)]
+pub fn print_clarifying_questions(_project_description: &str) {
+    /// Input: Takes in a short goal for a website build
+    /// Function: Lists the targeted questions whose answers would change how the website is built, most important first
+    /// Important: Only asks about what the goal leaves open, never about what it already says
+    /// Important: topic is "auth" for login and users, "persistence" for how data is stored, "data_source" for where external data comes from, and "other" for anything else
+    /// Important: default is the answer to assume when the user does not answer
+    /// Output: Prints ONLY a list response in the following format:
+    ///   [{ "topic": string, "question": string, "default": string }]
+    /// Example:
+    ///   project_description = "build a website that tracks crypto prices"
+    ///   prints:
+    ///   [
+    ///     { "topic": "data_source", "question": "Which exchange should prices come from?", "default": "Binance public API" },
+    ///     { "topic": "auth", "question": "Do users need accounts to keep a watchlist?", "default": "No accounts" },
+    ///     { "topic": "other", "question": "How often should prices refresh?", "default": "Every minute" }
+    ///   ]
+    println!(OUTPUT)
+}
+
+#[ai_function(output = Vec<String>)]
+pub fn print_requirements(_project_description: &str) {
+    /// Input: Takes in a project description of a website build
+    /// Function: Breaks the description into short, testable requirements, one thing the website must do each
+    /// Important: Every requirement is a single sentence about behaviour a user or client can observe through the API
+    /// Important: Does not number the requirements, they are numbered in the order printed
+    /// Output: Prints ONLY a list response of requirements in the following format:
+    /// ["requirement1", "requirement2", ...]
+    /// Example:
+    ///   project_description = "build a website that lets users keep a reading list"
+    ///   prints:
+    /// ["Users can register and log in", "Logged in users can add a book to their list", "Users can list their books", "Users can mark a book as finished", "Users can delete a book"]
+    println!(OUTPUT)
+}
+
+#[ai_function(output = Vec<String>)]
 pub fn print_site_urls(_project_description: &str) {
     /// Input: Takes in a project description of a website build
     /// Function: Outputs a list of external public API endpoints that should be used in the building of the website
@@ -2,27 +2,60 @@ use lib::function_to_string;
 
 #[function_to_string]
 pub fn print_backend_webserver_code(_project_description_and_template: &str) {
-    /// INPUT: Takes in a PROJECT_DESCRIPTION and CODE_TEMPLATE for a website backend build
+    /// INPUT: Takes in a PROJECT_DESCRIPTION, PROJECT_SCOPE and CODE_TEMPLATE for a website backend build, and EXTERNAL API SAMPLES when the project calls external urls
+    /// IMPORTANT: When a PROJECT_SCOPE is given, implements every entity with its fields, relationship and endpoint in it, stores data with its persistence ("json_file" or "sqlite"), protects routes with its auth_type, paginates and filters the lists it names and runs its scheduled_jobs with tokio
+    /// IMPORTANT: When REQUIREMENTS are given, every route handler function has a doc comment line "/// Requirements: R1, R2" with the ids of the requirements it implements
     /// IMPORTANT: The backend code is ONLY an example. If the Project Description requires it, make as many changes as you like.
     /// IMPORTANT: You do not need to follow the backend code exactly. Write functions that make sense for the users request if required.
     /// FUNCTION: Takes an existing set of code marked as CODE_TEMPLATE and updates or re-writes it to work for the purpose in the PROJECT_DESCRIPTION
     /// IMPORTANT: The following libraries are already installed
     ///   reqwest, serde, serde_json, tokio, actix-web, async-trait, actix_cors
-    /// No other external libraries should be used. Write functions that fit with the description from the PROJECT_DESCRIPTION
-    /// OUTPUT: Print ONLY the code, nothing else. This function ONLY prints code.
+    /// Only list another library in "dependencies" when the PROJECT_DESCRIPTION cannot be built without it. Write functions that fit with the description from the PROJECT_DESCRIPTION
+    /// IMPORTANT: Splits the code into modules, e.g. models, handlers and the server setup in main.rs
+    /// IMPORTANT: Structs that deserialize an external url response use exactly the field names and types of its JSON SCHEMA and SAMPLE RESPONSE. Fields that are not required are Option.
+    /// OUTPUT: Print ONLY a JSON project manifest, nothing else. This function ONLY prints the manifest:
+    ///   {
+    ///     "files": [
+    ///       { "path": "src/main.rs", "contents": "mod handlers;\nmod models;\n..." },
+    ///       { "path": "src/models.rs", "contents": "..." },
+    ///       { "path": "src/handlers.rs", "contents": "..." }
+    ///     ],
+    ///     "dependencies": [
+    ///       { "name": "chrono", "version": "0.4", "features": ["serde"] }
+    ///     ]
+    ///   }
+    /// IMPORTANT: Every path is relative to the project root and lives inside src/. src/main.rs declares every module with mod.
+    /// IMPORTANT: main.rs binds the server to 127.0.0.1 on the port read from the PORT environment variable, falling back to 8080 when it is not set.
+    /// IMPORTANT: The contents are JSON strings, so every newline and double quote inside the code is escaped.
     println!(OUTPUT)
 }
 
 #[function_to_string]
 pub fn print_improved_webserver_code(_project_description_and_template: &str) {
-    /// INPUT: Takes in a PROJECT_DESCRIPTION and CODE_TEMPLATE for a website backend build
+    /// INPUT: Takes in a PROJECT_DESCRIPTION, PROJECT_SCOPE and CODE_TEMPLATE for a website backend build
+    /// IMPORTANT: When a PROJECT_SCOPE is given, implements every entity with its fields, relationship and endpoint in it, stores data with its persistence ("json_file" or "sqlite"), protects routes with its auth_type, paginates and filters the lists it names and runs its scheduled_jobs with tokio
+    /// IMPORTANT: When REQUIREMENTS are given, every route handler function has a doc comment line "/// Requirements: R1, R2" with the ids of the requirements it implements
     /// FUNCTION: Performs the following tasks:
     ///   1. Removes any bugs in the code and adds minor additional functionality
     ///   2. Makes sure everything requested in the spec from a backend standpoint was followed. If not, add the feature. No code should be implemented later. Everything should be written now.
     ///   3. ONLY writes the code. No commentary.
     /// IMPORTANT: The following libraries are already installed. Does not use ANY libraries other than what was provided in the template
     ///   reqwest, serde, serde_json, tokio, actix-web, async-trait
-    /// OUTPUT: Print ONLY the code, nothing else. This function ONLY prints code.
+    /// IMPORTANT: Only lists the files that change. Files that are left out are kept as they are.
+    /// OUTPUT: Print ONLY a JSON project manifest, nothing else. This function ONLY prints the manifest:
+    ///   {
+    ///     "files": [
+    ///       { "path": "src/main.rs", "contents": "mod handlers;\nmod models;\n..." },
+    ///       { "path": "src/models.rs", "contents": "..." },
+    ///       { "path": "src/handlers.rs", "contents": "..." }
+    ///     ],
+    ///     "dependencies": [
+    ///       { "name": "chrono", "version": "0.4", "features": ["serde"] }
+    ///     ]
+    ///   }
+    /// IMPORTANT: Every path is relative to the project root and lives inside src/. src/main.rs declares every module with mod.
+    /// IMPORTANT: main.rs binds the server to 127.0.0.1 on the port read from the PORT environment variable, falling back to 8080 when it is not set.
+    /// IMPORTANT: The contents are JSON strings, so every newline and double quote inside the code is escaped.
     println!(OUTPUT)
 }
 
@@ -31,5 +64,60 @@ pub fn print_fixed_code(_broken_code_with_bugs: &str) {
     /// INPUT: Takes in Rust BROKEN_CODE and the ERROR_BUGS found
     /// FUNCTION: Removes bugs from code
     /// IMPORTANT: Only prints out the new and improved code. No commentary or anything else
+    /// IMPORTANT: Only lists the files that change. Files that are left out are kept as they are.
+    /// OUTPUT: Print ONLY a JSON project manifest, nothing else. This function ONLY prints the manifest:
+    ///   {
+    ///     "files": [
+    ///       { "path": "src/main.rs", "contents": "mod handlers;\nmod models;\n..." },
+    ///       { "path": "src/models.rs", "contents": "..." },
+    ///       { "path": "src/handlers.rs", "contents": "..." }
+    ///     ],
+    ///     "dependencies": [
+    ///       { "name": "chrono", "version": "0.4", "features": ["serde"] }
+    ///     ]
+    ///   }
+    /// IMPORTANT: Every path is relative to the project root and lives inside src/. src/main.rs declares every module with mod.
+    /// IMPORTANT: The contents are JSON strings, so every newline and double quote inside the code is escaped.
+    /// IMPORTANT: Keeps the "/// Requirements:" doc comments of route handlers as they are
+    println!(OUTPUT)
+}
+
+#[function_to_string]
+pub fn print_improved_webserver_code_edits(_project_description_and_code: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION, PROJECT_SCOPE and the CURRENT_CODE of a website backend build, every file starting with a FILE: path line
+    /// IMPORTANT: When a PROJECT_SCOPE is given, implements every entity with its fields, relationship and endpoint in it, stores data with its persistence ("json_file" or "sqlite"), protects routes with its auth_type, paginates and filters the lists it names and runs its scheduled_jobs with tokio
+    /// IMPORTANT: When REQUIREMENTS are given, every route handler function has a doc comment line "/// Requirements: R1, R2" with the ids of the requirements it implements
+    /// FUNCTION: Performs the following tasks:
+    ///   1. Removes any bugs in the code and adds minor additional functionality
+    ///   2. Makes sure everything requested in the spec from a backend standpoint was followed. If not, add the feature. No code should be implemented later. Everything should be written now.
+    ///   3. ONLY writes the changes. No commentary.
+    /// IMPORTANT: The following libraries are already installed. Does not use ANY libraries other than what was provided in the template
+    ///   reqwest, serde, serde_json, tokio, actix-web, async-trait
+    /// IMPORTANT: Never reprints the whole file. Every change is printed as the file path followed by a SEARCH/REPLACE block:
+    ///   FILE: src/main.rs
+    ///   <<<<<<< SEARCH
+    ///   lines copied exactly from CURRENT_CODE
+    ///   =======
+    ///   lines that replace them
+    ///   >>>>>>> REPLACE
+    /// IMPORTANT: The SEARCH lines must match that file of CURRENT_CODE exactly, including whitespace, and must only appear once in it
+    /// OUTPUT: Print ONLY the SEARCH/REPLACE blocks, nothing else.
+    println!(OUTPUT)
+}
+
+#[function_to_string]
+pub fn print_fixed_code_edits(_broken_code_with_bugs: &str) {
+    /// INPUT: Takes in Rust BROKEN_CODE, every file starting with a FILE: path line, and the ERROR_BUGS found
+    /// FUNCTION: Removes bugs from code by printing only the lines that change
+    /// IMPORTANT: Never reprints the whole file. Every change is printed as the file path followed by a SEARCH/REPLACE block:
+    ///   FILE: src/main.rs
+    ///   <<<<<<< SEARCH
+    ///   lines copied exactly from BROKEN_CODE
+    ///   =======
+    ///   lines that replace them
+    ///   >>>>>>> REPLACE
+    /// IMPORTANT: The SEARCH lines must match that file of BROKEN_CODE exactly, including whitespace, and must only appear once in it
+    /// IMPORTANT: Only prints out the SEARCH/REPLACE blocks. No commentary or anything else
+    /// IMPORTANT: Keeps the "/// Requirements:" doc comments of route handlers as they are
     println!(OUTPUT)
 }
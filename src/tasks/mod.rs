@@ -1,3 +1,38 @@
 pub mod analyst;
 pub mod backend;
 pub mod tester;
+
+#[cfg(test)]
+mod tests {
+    use super::{analyst::*, backend::*, tester::*};
+
+    type Prompt = fn(&str) -> &'static str;
+
+    // The exact prompts sent to the model, under src/tasks/snapshots
+    #[test]
+    fn test_task_prompts() {
+        let prompts: [(&str, Prompt); 11] = [
+            ("convert_user_input_to_goal", convert_user_input_to_goal),
+            ("print_project_scope", print_project_scope),
+            ("print_clarifying_questions", print_clarifying_questions),
+            ("print_requirements", print_requirements),
+            ("print_site_urls", print_site_urls),
+            ("print_backend_webserver_code", print_backend_webserver_code),
+            (
+                "print_improved_webserver_code",
+                print_improved_webserver_code,
+            ),
+            (
+                "print_improved_webserver_code_edits",
+                print_improved_webserver_code_edits,
+            ),
+            ("print_fixed_code", print_fixed_code),
+            ("print_fixed_code_edits", print_fixed_code_edits),
+            ("print_rest_api_endpoints", print_rest_api_endpoints),
+        ];
+
+        for (name, prompt) in prompts {
+            insta::assert_snapshot!(name, prompt(""));
+        }
+    }
+}
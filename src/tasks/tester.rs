@@ -1,6 +1,8 @@
-use lib::function_to_string;
+use lib::ai_function;
 
-#[function_to_string]
+use crate::models::agent::pro::pro_trait::RouteObject;
+
+#[ai_function(output = Vec<RouteObject>)]
 pub fn print_rest_api_endpoints(_code_input: &str) {
     /// INPUT: Takes in Rust webserver CODE_INPUT based on actix-web
     /// FUNCTION: Prints out the JSON schema for url endpoints and their respective types